@@ -1,17 +1,160 @@
 //! Attributes-related helpers.
 
-use syn::{spanned::Spanned, Expr, Lit, Meta, NestedMeta, Path, Type};
+use proc_macro2::Span;
+use syn::{
+    ext::IdentExt,
+    parenthesized,
+    parse::{Parse, ParseBuffer, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Expr, Ident, Lit, LitStr, Meta, NestedMeta, Token, Type, Visibility, WherePredicate,
+};
+
+use crate::error::Errors;
+
+/// Name of the attribute for `opaque-typedef`.
+const ATTR_NAME: &str = "opaque_typedef";
+
+/// Keys available only for type-level attributes.
+const TYPE_KEYS: &[&str] = &[
+    "allow_unchecked_mut",
+    "as_mut",
+    "as_ref",
+    "borrowed",
+    "bound",
+    "cmp",
+    "cmp_with",
+    "convert",
+    "deref",
+    "derive",
+    "fmt",
+    "from_str",
+    "hide_base_impl_docs",
+    "ops",
+    "owned",
+    "smart_ptr",
+    "unsafe_pair",
+    "validate",
+];
+
+/// Type-level keys available for composite types.
+///
+/// Other keys generate impls using the reference to the single inner value, which composite types
+/// do not have.
+const COMPOSITE_TYPE_KEYS: &[&str] = &["bound", "hide_base_impl_docs", "validate"];
+
+/// Keys in `fmt(..)` and the corresponding formatting traits.
+const FMT_TRAITS: &[(&str, &str)] = &[
+    ("display", "Display"),
+    ("lower_hex", "LowerHex"),
+    ("upper_hex", "UpperHex"),
+    ("octal", "Octal"),
+    ("binary", "Binary"),
+    ("lower_exp", "LowerExp"),
+    ("upper_exp", "UpperExp"),
+    ("pointer", "Pointer"),
+];
+
+/// Traits available in `derive(..)`, and the paths of the modules they belong to.
+const DERIVE_TRAITS: &[(&str, &str)] = &[
+    ("Clone", "clone"),
+    ("Copy", "marker"),
+    ("PartialEq", "cmp"),
+    ("Eq", "cmp"),
+    ("PartialOrd", "cmp"),
+    ("Ord", "cmp"),
+    ("Hash", "hash"),
+    ("Debug", "fmt"),
+];
+
+/// Operator traits available in `ops(..)`.
+const OP_TRAITS: &[OpTrait] = &[
+    OpTrait::new("add", "Add", "add", OpKind::Binary),
+    OpTrait::new("sub", "Sub", "sub", OpKind::Binary),
+    OpTrait::new("mul", "Mul", "mul", OpKind::Binary),
+    OpTrait::new("div", "Div", "div", OpKind::Binary),
+    OpTrait::new("rem", "Rem", "rem", OpKind::Binary),
+    OpTrait::new("neg", "Neg", "neg", OpKind::Unary),
+    OpTrait::new("add_assign", "AddAssign", "add_assign", OpKind::Assign),
+    OpTrait::new("sub_assign", "SubAssign", "sub_assign", OpKind::Assign),
+    OpTrait::new("mul_assign", "MulAssign", "mul_assign", OpKind::Assign),
+    OpTrait::new("div_assign", "DivAssign", "div_assign", OpKind::Assign),
+    OpTrait::new("rem_assign", "RemAssign", "rem_assign", OpKind::Assign),
+    OpTrait::new("bit_and", "BitAnd", "bitand", OpKind::Binary),
+    OpTrait::new("bit_or", "BitOr", "bitor", OpKind::Binary),
+    OpTrait::new("bit_xor", "BitXor", "bitxor", OpKind::Binary),
+    OpTrait::new("not", "Not", "not", OpKind::Unary),
+    OpTrait::new("shl", "Shl", "shl", OpKind::Binary),
+    OpTrait::new("shr", "Shr", "shr", OpKind::Binary),
+    OpTrait::new(
+        "bit_and_assign",
+        "BitAndAssign",
+        "bitand_assign",
+        OpKind::Assign,
+    ),
+    OpTrait::new(
+        "bit_or_assign",
+        "BitOrAssign",
+        "bitor_assign",
+        OpKind::Assign,
+    ),
+    OpTrait::new(
+        "bit_xor_assign",
+        "BitXorAssign",
+        "bitxor_assign",
+        OpKind::Assign,
+    ),
+    OpTrait::new("shl_assign", "ShlAssign", "shl_assign", OpKind::Assign),
+    OpTrait::new("shr_assign", "ShrAssign", "shr_assign", OpKind::Assign),
+];
+
+/// Traits available in `bound(..)`, other than the ones in `DERIVE_TRAITS`, `FMT_TRAITS`, and
+/// `OP_TRAITS`.
+///
+/// These are all the traits implemented by the derives and the attributes.
+const BOUND_TRAITS: &[&str] = &[
+    "AsMut",
+    "AsRef",
+    "Borrow",
+    "Deref",
+    "DerefMut",
+    "From",
+    "FromStr",
+    "OpaqueTypedefComposite",
+    "OpaqueTypedefOwned",
+    "OpaqueTypedefSized",
+    "OpaqueTypedefSizedInfallible",
+    "OpaqueTypedefSizedMut",
+    "OpaqueTypedefUnsized",
+    "OpaqueTypedefUnsizedInfallible",
+    "OpaqueTypedefUnsizedInfallibleMut",
+    "OpaqueTypedefUnsizedMut",
+    "ToOwned",
+    "TransparentUnsized",
+    "TryFrom",
+];
+
+/// Returns the names of the traits available in `bound(..)`, sorted and deduplicated.
+pub fn bound_trait_names() -> Vec<&'static str> {
+    let mut names = BOUND_TRAITS
+        .iter()
+        .copied()
+        .chain(DERIVE_TRAITS.iter().map(|(name, _)| *name))
+        .chain(FMT_TRAITS.iter().map(|(_, name)| *name))
+        .chain(OP_TRAITS.iter().map(|op| op.name))
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Keys available only for field-level attributes.
+const FIELD_KEYS: &[&str] = &["getter", "init", "inner"];
 
 /// Extension for `syn::Meta` type.
 pub trait MetaExt {
     /// Returns `true` if the meta has the repr `C` or `transparent`.
     fn has_unsized_repr(&self) -> bool;
-    /// Returns `true` if the meta has the word at depth 2, such as `foo(bar)` or path `foo::bar`.
-    fn has_level2_word(&self, level1: &str, level2: &str) -> bool;
-    /// Returns validator if available.
-    fn validator(&self) -> Result<Option<Expr>, syn::Error>;
-    /// Returns validation error type if available.
-    fn ty_validation_error(&self) -> Result<Option<Type>, syn::Error>;
 }
 
 impl MetaExt for Meta {
@@ -28,123 +171,1287 @@ impl MetaExt for Meta {
             _ => false,
         }
     }
+}
 
-    fn has_level2_word(&self, level1: &str, level2: &str) -> bool {
-        // NOTE:
-        // Though this impl supports both `#[foo::bar]` and `#[foo(bar)]`, type level attribute in
-        // `#[foo::bar]` syntax is not intended (and not supported) by Rust 1.37.
-        // See <https://github.com/rust-lang/rust/issues/55168>.
-        match self {
-            Meta::Path(path) => eq_path_components(path, &[level1, level2]),
-            Meta::List(metalist) if metalist.path.is_ident(level1) => {
-                metalist.nested.iter().any(|nested| match nested {
-                    NestedMeta::Meta(Meta::Path(path)) => path.is_ident(level2),
-                    _ => false,
-                })
+/// Type-level `#[opaque_typedef(..)]` attributes.
+#[derive(Default)]
+pub struct TypeAttrs {
+    /// All keys specified, in order.
+    keys: Vec<Ident>,
+    /// `hide_base_impl_docs`.
+    hide_base_impl_docs: Option<Span>,
+    /// `allow_unchecked_mut`.
+    allow_unchecked_mut: Option<Span>,
+    /// `validate(..)`.
+    validate: Option<ValidateAttr>,
+    /// `bound = ..` and `bound(..)`.
+    bound: BoundAttr,
+    /// Targets of `as_ref(..)`.
+    as_ref: Vec<RefTarget>,
+    /// Targets of `as_mut(..)`.
+    as_mut: Vec<RefTarget>,
+    /// `deref(..)`.
+    deref: Option<DerefAttr>,
+    /// `derive(..)`.
+    derive: DeriveAttr,
+    /// `fmt(..)`.
+    fmt: FmtAttr,
+    /// `ops(..)`.
+    ops: OpsAttr,
+    /// `from_str`.
+    from_str: Option<Span>,
+    /// `convert(..)`.
+    convert: ConvertAttr,
+    /// `smart_ptr(..)`.
+    smart_ptr: SmartPtrAttr,
+    /// `owned = ..` or `borrowed = ..`.
+    pair: Option<PairAttr>,
+    /// `unsafe_pair`.
+    unsafe_pair: Option<Span>,
+    /// `cmp(..)`.
+    cmp: Option<CmpKey>,
+    /// Targets of `cmp_with(..)`.
+    cmp_with: Vec<RefTarget>,
+    /// `base = ..` in `cmp_with(..)`.
+    cmp_with_base: Option<Type>,
+}
+
+impl TypeAttrs {
+    /// Parses the type-level attributes.
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut this = Self::default();
+        parse_attrs(attrs, |key, input| {
+            this.keys.push(key.clone());
+            this.parse_item(key, input)
+        })?;
+        Ok(this)
+    }
+
+    /// Ensures that only the keys available for composite types are specified.
+    pub fn ensure_composite_compatible(&self) -> syn::Result<()> {
+        let mut errors = Errors::new();
+        for key in &self.keys {
+            if !COMPOSITE_TYPE_KEYS.contains(&key.to_string().as_str()) {
+                errors.push(syn::Error::new(
+                    key.span(),
+                    format!("`{}` is not available for composite types", key),
+                ));
             }
-            _ => false,
         }
+        for key in self.bound.trait_keys() {
+            errors.push(syn::Error::new(
+                key.span(),
+                "`bound(..)` for specific traits is not available for composite types",
+            ));
+        }
+        errors.finish()
     }
 
-    fn validator(&self) -> Result<Option<Expr>, syn::Error> {
-        find_validation_metas(self)
-            .find_map(|meta| match meta {
-                Meta::NameValue(namevalue) if namevalue.path.is_ident("validator") => {
-                    Some(&namevalue.lit)
+    /// Parses an item of the type-level attribute.
+    fn parse_item(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        match key.to_string().as_str() {
+            "hide_base_impl_docs" => {
+                parse_word(key, input)?;
+                set_once(&mut self.hide_base_impl_docs, key, key.span())
+            }
+            "allow_unchecked_mut" => {
+                parse_word(key, input)?;
+                set_once(&mut self.allow_unchecked_mut, key, key.span())
+            }
+            "validate" => {
+                let validate = ValidateAttr::parse(key, input)?;
+                set_once(&mut self.validate, key, validate)
+            }
+            "bound" => self.bound.parse(key, input),
+            "as_ref" => {
+                self.as_ref.extend(RefTarget::parse_list(key, input)?);
+                Ok(())
+            }
+            "as_mut" => {
+                self.as_mut.extend(RefTarget::parse_list(key, input)?);
+                Ok(())
+            }
+            "deref" => {
+                let deref = DerefAttr::parse(key, input)?;
+                set_once(&mut self.deref, key, deref)
+            }
+            "cmp" => {
+                let cmp = CmpKey::parse(key, input)?;
+                set_once(&mut self.cmp, key, cmp)
+            }
+            "cmp_with" => self.parse_cmp_with(key, input),
+            "derive" => self.derive.parse(key, input),
+            "fmt" => self.fmt.parse(key, input),
+            "ops" => self.ops.parse(key, input),
+            "convert" => self.convert.parse(key, input),
+            "smart_ptr" => self.smart_ptr.parse(key, input),
+            "owned" | "borrowed" => {
+                let ty = parse_value(key, input, "type")?;
+                let pair = PairAttr {
+                    key: key.clone(),
+                    ty,
+                };
+                if self.pair.is_some() {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "Only one of `owned` and `borrowed` can be specified",
+                    ));
                 }
-                _ => None,
-            })
-            .map(|lit| match lit {
-                Lit::Str(s) => s.parse().map_err(|e| {
-                    syn::Error::new(
-                        lit.span(),
-                        format!("Failed to parse validator function: {}", e),
-                    )
-                }),
-                _ => Err(syn::Error::new(
-                    lit.span(),
-                    "Expected string literal as validator, but got other literal",
-                )),
-            })
-            .transpose()
+                self.pair = Some(pair);
+                Ok(())
+            }
+            "unsafe_pair" => {
+                parse_word(key, input)?;
+                set_once(&mut self.unsafe_pair, key, key.span())
+            }
+            "from_str" => {
+                parse_word(key, input)?;
+                set_once(&mut self.from_str, key, key.span())
+            }
+            _ => Err(unknown_key_error(key, FIELD_KEYS, "field")),
+        }
     }
 
-    fn ty_validation_error(&self) -> Result<Option<Type>, syn::Error> {
-        find_validation_metas(self)
-            .find_map(|meta| match meta {
-                Meta::NameValue(namevalue) if namevalue.path.is_ident("error") => {
-                    Some(&namevalue.lit)
+    /// Parses `cmp_with(..)`.
+    fn parse_cmp_with(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        let content = parse_list(key, input)?;
+        let len = self.cmp_with.len();
+        while !content.is_empty() {
+            if content.peek(Ident::peek_any) && content.peek2(Token![=]) {
+                let key = content.call(Ident::parse_any)?;
+                if key != "base" {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Unknown key `{}` for `cmp_with`, expected `base`", key),
+                    ));
                 }
-                _ => None,
-            })
-            .map(|lit| match lit {
-                Lit::Str(s) => s.parse().map_err(|e| {
-                    syn::Error::new(
-                        lit.span(),
-                        format!("Failed to parse validation error type: {}", e),
-                    )
-                }),
-                _ => Err(syn::Error::new(
-                    lit.span(),
-                    "Expected string literal as validation error type, but got other literal",
-                )),
-            })
-            .transpose()
+                let base = parse_value(&key, &content, "base type")?;
+                set_once(&mut self.cmp_with_base, &key, base)?;
+            } else {
+                match RefTarget::parse(&content)? {
+                    RefTarget::SelfType(span) => {
+                        return Err(syn::Error::new(
+                            span,
+                            "`self` is not available as the target of `cmp_with`",
+                        ))
+                    }
+                    target => self.cmp_with.push(target),
+                }
+            }
+            if content.is_empty() {
+                break;
+            }
+            content.parse::<Token![,]>()?;
+        }
+        if self.cmp_with.len() == len {
+            return Err(syn::Error::new(
+                key.span(),
+                format!("Expected at least one target type for `{}(..)`", key),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns whether the base traits impls docs should be hidden.
+    pub fn hide_base_impl_docs(&self) -> bool {
+        self.hide_base_impl_docs.is_some()
     }
+
+    /// Returns whether unchecked mutable access to the validated inner value is allowed.
+    pub fn allow_unchecked_mut(&self) -> bool {
+        self.allow_unchecked_mut.is_some()
+    }
+
+    /// Returns the validator if available.
+    pub fn validator(&self) -> Option<&Expr> {
+        self.validate.as_ref().and_then(|v| v.validator.as_ref())
+    }
+
+    /// Returns the validation error type if available.
+    pub fn ty_validation_error(&self) -> Option<&Type> {
+        self.validate.as_ref().and_then(|v| v.error.as_ref())
+    }
+
+    /// Returns the targets of `AsRef` impls.
+    pub fn as_ref_targets(&self) -> &[RefTarget] {
+        &self.as_ref
+    }
+
+    /// Returns the targets of `AsMut` impls.
+    pub fn as_mut_targets(&self) -> &[RefTarget] {
+        &self.as_mut
+    }
+
+    /// Returns the targets of `cmp_with(..)`.
+    pub fn cmp_with_targets(&self) -> &[RefTarget] {
+        &self.cmp_with
+    }
+
+    /// Returns the base type of the comparison by `cmp_with(..)` if specified.
+    pub fn cmp_with_base(&self) -> Option<&Type> {
+        self.cmp_with_base.as_ref()
+    }
+
+    /// Returns `cmp(..)` if available.
+    pub fn cmp(&self) -> Option<&CmpKey> {
+        self.cmp.as_ref()
+    }
+
+    /// Returns `bound = ..` and `bound(..)`.
+    pub fn bound(&self) -> &BoundAttr {
+        &self.bound
+    }
+
+    /// Returns `derive(..)`.
+    pub fn derive(&self) -> &DeriveAttr {
+        &self.derive
+    }
+
+    /// Returns `fmt(..)`.
+    pub fn fmt(&self) -> &FmtAttr {
+        &self.fmt
+    }
+
+    /// Returns the span of `from_str` if specified.
+    pub fn impl_from_str(&self) -> Option<Span> {
+        self.from_str
+    }
+
+    /// Returns `convert(..)`.
+    pub fn convert(&self) -> &ConvertAttr {
+        &self.convert
+    }
+
+    /// Returns `smart_ptr(..)`.
+    pub fn smart_ptr(&self) -> &SmartPtrAttr {
+        &self.smart_ptr
+    }
+
+    /// Returns `owned = ..` or `borrowed = ..` if available.
+    pub fn pair(&self) -> Option<&PairAttr> {
+        self.pair.as_ref()
+    }
+
+    /// Returns the span of `unsafe_pair` if specified.
+    pub fn unsafe_pair(&self) -> Option<Span> {
+        self.unsafe_pair
+    }
+
+    /// Returns `ops(..)`.
+    pub fn ops(&self) -> &OpsAttr {
+        &self.ops
+    }
+
+    /// Returns `deref(..)` if available.
+    pub fn deref(&self) -> Option<&DerefAttr> {
+        self.deref.as_ref()
+    }
+}
+
+/// Target type of `as_ref(..)` and `as_mut(..)`.
+pub enum RefTarget {
+    /// `inner`, i.e. the inner type.
+    Inner(Span),
+    /// `self`, i.e. the type itself.
+    SelfType(Span),
+    /// Other type which the inner type can be converted to.
+    Type(Box<Type>),
 }
 
-/// Find `#[(opaque_typedef(validate(**Metas HERE**))]`.
-fn find_validation_metas(meta: &Meta) -> impl Iterator<Item = &Meta> {
-    std::iter::once(meta)
-        .filter_map(|meta| match meta {
-            Meta::List(metalist) if metalist.path.is_ident("opaque_typedef") => {
-                Some(&metalist.nested)
+impl RefTarget {
+    /// Parses comma-separated targets in `(..)` following the key.
+    fn parse_list(key: &Ident, input: ParseStream) -> syn::Result<Vec<Self>> {
+        let content = parse_list(key, input)?;
+        let targets = content.parse_terminated::<_, Token![,]>(Self::parse)?;
+        if targets.is_empty() {
+            return Err(syn::Error::new(
+                key.span(),
+                format!("Expected at least one target type for `{}(..)`", key),
+            ));
+        }
+        Ok(targets.into_iter().collect())
+    }
+
+    /// Parses a target.
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![self]) {
+            let self_token = input.parse::<Token![self]>()?;
+            return Ok(RefTarget::SelfType(self_token.span));
+        }
+        let ty = if input.peek(LitStr) {
+            parse_lit_str(&input.parse()?, "target type")?
+        } else {
+            input.parse()?
+        };
+        match &ty {
+            Type::Path(path) if path.qself.is_none() && path.path.is_ident("inner") => {
+                Ok(RefTarget::Inner(ty.span()))
             }
-            _ => None,
-        })
-        .flat_map(|nested_list| nested_list)
-        .filter_map(|nested| match nested {
-            NestedMeta::Meta(Meta::List(metalist)) if metalist.path.is_ident("validate") => {
-                Some(&metalist.nested)
+            _ => Ok(RefTarget::Type(Box::new(ty))),
+        }
+    }
+
+    /// Returns the span of the target.
+    pub fn span(&self) -> Span {
+        match self {
+            RefTarget::Inner(span) | RefTarget::SelfType(span) => *span,
+            RefTarget::Type(ty) => ty.span(),
+        }
+    }
+}
+
+/// `deref(..)` in the type-level attribute.
+#[derive(Default)]
+pub struct DerefAttr {
+    /// `target = ..`.
+    target: Option<RefTarget>,
+    /// `mut`.
+    mutable: Option<Span>,
+}
+
+impl DerefAttr {
+    /// Parses `deref(..)`.
+    fn parse(key: &Ident, input: ParseStream) -> syn::Result<Self> {
+        let content = parse_list(key, input)?;
+        let mut this = Self::default();
+        parse_items(&content, |key, input| match key.to_string().as_str() {
+            "target" => {
+                if !input.peek(Token![=]) {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Expected `{} = ..`", key),
+                    ));
+                }
+                input.parse::<Token![=]>()?;
+                let target = match RefTarget::parse(input)? {
+                    RefTarget::SelfType(span) => {
+                        return Err(syn::Error::new(
+                            span,
+                            "`self` is not available as the target of `deref`",
+                        ))
+                    }
+                    target => target,
+                };
+                set_once(&mut this.target, key, target)
+            }
+            "mut" => {
+                parse_word(key, input)?;
+                set_once(&mut this.mutable, key, key.span())
             }
+            _ => Err(syn::Error::new(
+                key.span(),
+                format!(
+                    "Unknown key `{}` for `deref`, expected `target` or `mut`",
+                    key
+                ),
+            )),
+        })?;
+        Ok(this)
+    }
+
+    /// Returns the deref target, or `None` if the target is the inner type.
+    pub fn target(&self) -> Option<&Type> {
+        match &self.target {
+            Some(RefTarget::Type(ty)) => Some(ty),
             _ => None,
+        }
+    }
+
+    /// Returns the span of `mut` if `DerefMut` is requested.
+    pub fn mutable(&self) -> Option<Span> {
+        self.mutable
+    }
+}
+
+/// `cmp(..)` in the type-level attribute, i.e. the comparison key.
+pub enum CmpKey {
+    /// Key function, which takes a reference to the inner value.
+    Fn(Box<Expr>),
+    /// ASCII case-insensitive comparison.
+    AsciiCaseInsensitive,
+}
+
+impl CmpKey {
+    /// Parses `cmp(..)`.
+    fn parse(key: &Ident, input: ParseStream) -> syn::Result<Self> {
+        let content = parse_list(key, input)?;
+        let mut cmp_key = None;
+        parse_items(&content, |key, input| {
+            let value = match key.to_string().as_str() {
+                "key" => CmpKey::Fn(Box::new(parse_value(key, input, "key function")?)),
+                "ascii_case_insensitive" => {
+                    parse_word(key, input)?;
+                    CmpKey::AsciiCaseInsensitive
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!(
+                        "Unknown key `{}` for `cmp`, expected `key` or `ascii_case_insensitive`",
+                        key
+                    ),
+                    ))
+                }
+            };
+            if cmp_key.is_some() {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "Only one of `key` and `ascii_case_insensitive` can be specified",
+                ));
+            }
+            cmp_key = Some(value);
+            Ok(())
+        })?;
+        cmp_key.ok_or_else(|| {
+            syn::Error::new(
+                key.span(),
+                "`key` or `ascii_case_insensitive` is required for `cmp`",
+            )
         })
-        .flat_map(|nested_list| {
-            nested_list.iter().filter_map(|nested| match nested {
-                NestedMeta::Meta(meta) => Some(meta),
-                _ => None,
-            })
+    }
+}
+
+/// `bound = ..` and `bound(..)` in the type-level attribute.
+#[derive(Default)]
+pub struct BoundAttr {
+    /// Additional predicates for all impls.
+    common: Option<Vec<WherePredicate>>,
+    /// Predicates for the impls of the specific traits.
+    traits: Vec<(Ident, Vec<WherePredicate>)>,
+}
+
+impl BoundAttr {
+    /// Parses `bound = ".."` or `bound(Trait = "..", ..)`, and merges the items into `self`.
+    fn parse(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        if input.peek(Token![=]) {
+            let predicates = parse_predicates(key, input)?;
+            return set_once(&mut self.common, key, predicates);
+        }
+        let content = parse_list(key, input)?;
+        parse_items(&content, |key, input| {
+            let names = bound_trait_names();
+            if !names.contains(&key.to_string().as_str()) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!(
+                        "Unknown trait `{}` for `bound(..)`, expected one of {}",
+                        key,
+                        names
+                            .iter()
+                            .map(|name| format!("`{}`", name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+            let predicates = parse_predicates(key, input)?;
+            if self.traits.iter().any(|(name, _)| name == key) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("Duplicate bound for `{}` is specified", key),
+                ));
+            }
+            self.traits.push((key.clone(), predicates));
+            Ok(())
         })
+    }
+
+    /// Returns the additional predicates for all impls.
+    pub fn common(&self) -> &[WherePredicate] {
+        self.common.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the trait names of `bound(Trait = "..")`.
+    pub fn trait_keys(&self) -> impl Iterator<Item = &Ident> {
+        self.traits.iter().map(|(name, _)| name)
+    }
+
+    /// Returns the predicates for the impls of the given trait, if specified.
+    pub fn for_trait(&self, name: &str) -> Option<&[WherePredicate]> {
+        self.traits
+            .iter()
+            .find(|(v, _)| v == name)
+            .map(|(_, predicates)| &predicates[..])
+    }
+}
+
+/// Parses `= "predicates"` following the key.
+///
+/// Unlike other values, predicates should be a string literal, because they can contain commas.
+fn parse_predicates(key: &Ident, input: ParseStream) -> syn::Result<Vec<WherePredicate>> {
+    if !input.peek(Token![=]) || !input.peek2(LitStr) {
+        return Err(syn::Error::new(
+            key.span(),
+            format!("Expected `{} = \"..\"`", key),
+        ));
+    }
+    input.parse::<Token![=]>()?;
+    let lit = input.parse::<LitStr>()?;
+    let predicates = lit
+        .parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)
+        .map_err(|e| {
+            syn::Error::new(
+                lit.span(),
+                format!("Failed to parse where predicates: {}", e),
+            )
+        })?;
+    Ok(predicates.into_iter().collect())
+}
+
+/// `derive(..)` in the type-level attribute.
+#[derive(Default)]
+pub struct DeriveAttr {
+    /// Traits to be derived, with the module names and the spans.
+    traits: Vec<(&'static str, &'static str, Span)>,
 }
 
-/// Checks if the given path consists with the given idents (given as `&str`).
-fn eq_path_components(path: &Path, components: &[&str]) -> bool {
-    path.segments.len() == components.len()
-        && path
-            .segments
+impl DeriveAttr {
+    /// Parses `derive(..)`, and merges the items into `self`.
+    fn parse(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        let content = parse_list(key, input)?;
+        parse_items(&content, |key, input| {
+            let key_str = key.to_string();
+            let (name, module) = match DERIVE_TRAITS.iter().find(|(name, _)| *name == key_str) {
+                Some(v) => *v,
+                None => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Unsupported trait `{}` for `derive`", key),
+                    ))
+                }
+            };
+            parse_word(key, input)?;
+            if self.get(name).is_some() {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("Duplicate `{}` is specified", key),
+                ));
+            }
+            self.traits.push((name, module, key.span()));
+            Ok(())
+        })
+    }
+
+    /// Returns the traits to be derived, with the names of the modules in `std`.
+    pub fn traits(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        self.traits.iter().map(|&(name, module, _)| (name, module))
+    }
+
+    /// Returns the span of the given trait if it is specified.
+    pub fn get(&self, name: &str) -> Option<Span> {
+        self.traits
             .iter()
-            .zip(components)
-            .all(|(seg, comp)| match seg.arguments {
-                syn::PathArguments::None => seg.ident == comp,
-                _ => false,
-            })
+            .find(|(v, _, _)| *v == name)
+            .map(|&(_, _, span)| span)
+    }
+}
+
+/// `fmt(..)` in the type-level attribute.
+#[derive(Default)]
+pub struct FmtAttr {
+    /// Names of the formatting traits to be implemented.
+    traits: Vec<&'static str>,
+    /// `debug = ..`.
+    debug: Option<DebugMode>,
+}
+
+impl FmtAttr {
+    /// Parses `fmt(..)`, and merges the items into `self`.
+    fn parse(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        let content = parse_list(key, input)?;
+        parse_items(&content, |key, input| {
+            let key_str = key.to_string();
+            if key_str == "debug" {
+                let mode: Ident = parse_value(key, input, "debug mode")?;
+                let mode = match mode.to_string().as_str() {
+                    "transparent" => DebugMode::Transparent,
+                    "named" => DebugMode::Named,
+                    _ => {
+                        return Err(syn::Error::new(
+                            mode.span(),
+                            format!(
+                                "Unknown debug mode `{}`, expected `transparent` or `named`",
+                                mode
+                            ),
+                        ))
+                    }
+                };
+                return set_once(&mut self.debug, key, mode);
+            }
+            let name = match FMT_TRAITS.iter().find(|(k, _)| *k == key_str) {
+                Some((_, name)) => *name,
+                None => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Unknown key `{}` for `fmt`", key),
+                    ))
+                }
+            };
+            parse_word(key, input)?;
+            if self.traits.contains(&name) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("Duplicate `{}` is specified", key),
+                ));
+            }
+            self.traits.push(name);
+            Ok(())
+        })
+    }
+
+    /// Returns the names of the formatting traits in `std::fmt` to be implemented.
+    pub fn traits(&self) -> &[&'static str] {
+        &self.traits
+    }
+
+    /// Returns the mode of `Debug` impl if specified.
+    pub fn debug(&self) -> Option<DebugMode> {
+        self.debug
+    }
+}
+
+/// Mode of `Debug` impl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugMode {
+    /// Same as the inner value.
+    Transparent,
+    /// Same as the tuple struct with the type name and the inner value.
+    Named,
+}
+
+/// `convert(..)` in the type-level attribute.
+#[derive(Default)]
+pub struct ConvertAttr {
+    /// `from_inner`.
+    from_inner: Option<Span>,
+    /// `into_inner`.
+    into_inner: Option<Span>,
+}
+
+impl ConvertAttr {
+    /// Parses `convert(..)`, and merges the items into `self`.
+    fn parse(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        let content = parse_list(key, input)?;
+        parse_items(&content, |key, input| {
+            let slot = match key.to_string().as_str() {
+                "from_inner" => &mut self.from_inner,
+                "into_inner" => &mut self.into_inner,
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Unknown key `{}` for `convert`", key),
+                    ))
+                }
+            };
+            parse_word(key, input)?;
+            set_once(slot, key, key.span())
+        })
+    }
+
+    /// Returns whether the conversion from the inner type should be implemented.
+    pub fn impl_from_inner(&self) -> bool {
+        self.from_inner.is_some()
+    }
+
+    /// Returns whether the conversion into the inner type should be implemented.
+    pub fn impl_into_inner(&self) -> bool {
+        self.into_inner.is_some()
+    }
+}
+
+/// `smart_ptr(..)` in the type-level attribute.
+#[derive(Default)]
+pub struct SmartPtrAttr {
+    /// Span of the first `smart_ptr` key.
+    span: Option<Span>,
+    /// Smart pointers to be supported.
+    ptrs: Vec<SmartPtr>,
+}
+
+impl SmartPtrAttr {
+    /// Parses `smart_ptr(..)`, and merges the items into `self`.
+    fn parse(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        let content = parse_list(key, input)?;
+        self.span.get_or_insert(key.span());
+        parse_items(&content, |key, input| {
+            let ptr = match key.to_string().as_str() {
+                "box" => SmartPtr::Box,
+                "rc" => SmartPtr::Rc,
+                "arc" => SmartPtr::Arc,
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Unknown key `{}` for `smart_ptr`", key),
+                    ))
+                }
+            };
+            parse_word(key, input)?;
+            if self.ptrs.contains(&ptr) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("Duplicate `{}` is specified", key),
+                ));
+            }
+            self.ptrs.push(ptr);
+            Ok(())
+        })
+    }
+
+    /// Returns the span of the first `smart_ptr` key if specified.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Returns the smart pointers to be supported.
+    pub fn ptrs(&self) -> &[SmartPtr] {
+        &self.ptrs
+    }
+}
+
+/// Smart pointer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmartPtr {
+    /// `Box`.
+    Box,
+    /// `Rc`.
+    Rc,
+    /// `Arc`.
+    Arc,
+}
+
+/// `owned = ..` or `borrowed = ..` in the type-level attribute.
+pub struct PairAttr {
+    /// Key, i.e. `owned` or `borrowed`.
+    key: Ident,
+    /// Type of the counterpart.
+    ty: Type,
+}
+
+impl PairAttr {
+    /// Returns the key.
+    pub fn key(&self) -> &Ident {
+        &self.key
+    }
+
+    /// Returns the type of the counterpart, i.e. the owned type or the borrowed type.
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+}
+
+/// `ops(..)` in the type-level attribute.
+#[derive(Default)]
+pub struct OpsAttr {
+    /// Span of the first `ops` key.
+    span: Option<Span>,
+    /// Operator traits to be implemented.
+    traits: Vec<OpAttr>,
+    /// `mask`.
+    mask: Option<Span>,
+    /// `on_invalid = ..`.
+    on_invalid: Option<(Span, OnInvalid)>,
+}
+
+impl OpsAttr {
+    /// Parses `ops(..)`, and merges the items into `self`.
+    fn parse(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        let content = parse_list(key, input)?;
+        self.span.get_or_insert(key.span());
+        parse_items(&content, |key, input| {
+            let key_str = key.to_string();
+            if key_str == "on_invalid" {
+                let mode: Ident = parse_value(key, input, "behavior on invalid result")?;
+                let mode = match mode.to_string().as_str() {
+                    "panic" => OnInvalid::Panic,
+                    "result" => OnInvalid::Result,
+                    _ => {
+                        return Err(syn::Error::new(
+                            mode.span(),
+                            format!(
+                                "Unknown behavior `{}` on invalid result, \
+                                 expected `panic` or `result`",
+                                mode
+                            ),
+                        ))
+                    }
+                };
+                return set_once(&mut self.on_invalid, key, (key.span(), mode));
+            }
+            if key_str == "mask" {
+                parse_word(key, input)?;
+                return set_once(&mut self.mask, key, key.span());
+            }
+            let op = match OP_TRAITS.iter().find(|op| op.key == key_str) {
+                Some(op) => op,
+                None => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Unknown key `{}` for `ops`", key),
+                    ))
+                }
+            };
+            let rhs = if op.is_shift() {
+                Some(parse_shift_rhs(key, input)?)
+            } else {
+                parse_word(key, input)?;
+                None
+            };
+            if self.traits.iter().any(|v| v.op.key == op.key) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("Duplicate `{}` is specified", key),
+                ));
+            }
+            self.traits.push(OpAttr { op, rhs });
+            Ok(())
+        })
+    }
+
+    /// Returns the span of the first `ops` key if specified.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Returns the operator traits to be implemented.
+    pub fn traits(&self) -> &[OpAttr] {
+        &self.traits
+    }
+
+    /// Returns whether the helper methods for bit masks should be generated.
+    pub fn mask(&self) -> bool {
+        self.mask.is_some()
+    }
+
+    /// Returns the span and the behavior on invalid result if specified.
+    pub fn on_invalid(&self) -> Option<(Span, OnInvalid)> {
+        self.on_invalid
+    }
+}
+
+/// Parses `(rhs = ..)` following the key of a shift operator.
+///
+/// Shift operators usually take an integer of a different type as the right operand, so the
+/// type should be specified explicitly.
+fn parse_shift_rhs(key: &Ident, input: ParseStream) -> syn::Result<Type> {
+    if !input.peek(syn::token::Paren) {
+        return Err(syn::Error::new(
+            key.span(),
+            format!(
+                "Expected the right operand type for `{0}`, e.g. `{0}(rhs = \"u32\")`",
+                key
+            ),
+        ));
+    }
+    let content = parse_list(key, input)?;
+    let mut rhs = None;
+    parse_items(&content, |item, input| {
+        if item != "rhs" {
+            return Err(syn::Error::new(
+                item.span(),
+                format!("Unknown key `{}` for `{}`", item, key),
+            ));
+        }
+        let ty = parse_value(item, input, "right operand type")?;
+        set_once(&mut rhs, item, ty)
+    })?;
+    rhs.ok_or_else(|| syn::Error::new(key.span(), format!("Expected `rhs = ..` for `{}(..)`", key)))
+}
+
+/// Operator trait with its options in `ops(..)`.
+pub struct OpAttr {
+    /// Operator trait.
+    op: &'static OpTrait,
+    /// `rhs = ..` of shift operators.
+    rhs: Option<Type>,
+}
+
+impl OpAttr {
+    /// Returns the operator trait.
+    pub fn op(&self) -> &'static OpTrait {
+        self.op
+    }
+
+    /// Returns the right operand type of shift operators.
+    pub fn rhs(&self) -> Option<&Type> {
+        self.rhs.as_ref()
+    }
+}
+
+/// Operator trait.
+#[derive(Debug)]
+pub struct OpTrait {
+    /// Key in `ops(..)`.
+    key: &'static str,
+    /// Trait name in `std::ops`.
+    name: &'static str,
+    /// Method name.
+    method: &'static str,
+    /// Operator kind.
+    kind: OpKind,
+}
+
+impl OpTrait {
+    /// Creates a new `OpTrait`.
+    const fn new(
+        key: &'static str,
+        name: &'static str,
+        method: &'static str,
+        kind: OpKind,
+    ) -> Self {
+        Self {
+            key,
+            name,
+            method,
+            kind,
+        }
+    }
+
+    /// Returns the trait name in `std::ops`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the method name.
+    pub fn method(&self) -> &'static str {
+        self.method
+    }
+
+    /// Returns the operator kind.
+    pub fn kind(&self) -> OpKind {
+        self.kind
+    }
+
+    /// Returns whether the operator is a shift operator.
+    fn is_shift(&self) -> bool {
+        matches!(self.key, "shl" | "shr" | "shl_assign" | "shr_assign")
+    }
+}
+
+/// Kind of operator traits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpKind {
+    /// Unary operator such as `Neg`.
+    Unary,
+    /// Binary operator such as `Add`.
+    Binary,
+    /// Compound assignment operator such as `AddAssign`.
+    Assign,
+}
+
+/// Behavior of operators on invalid result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnInvalid {
+    /// Panics.
+    Panic,
+    /// Returns `Result<Self, Self::Error>`.
+    Result,
+}
+
+/// `validate(..)` in the type-level attribute.
+#[derive(Default)]
+struct ValidateAttr {
+    /// `validator = ..`.
+    validator: Option<Expr>,
+    /// `error = ..`.
+    error: Option<Type>,
+}
+
+impl ValidateAttr {
+    /// Parses `validate(..)`.
+    fn parse(key: &Ident, input: ParseStream) -> syn::Result<Self> {
+        let content = parse_list(key, input)?;
+        let mut this = Self::default();
+        parse_items(&content, |key, input| match key.to_string().as_str() {
+            "validator" => {
+                let validator = parse_value(key, input, "validator function")?;
+                set_once(&mut this.validator, key, validator)
+            }
+            "error" => {
+                let error = parse_value(key, input, "validation error type")?;
+                set_once(&mut this.error, key, error)
+            }
+            _ => Err(syn::Error::new(
+                key.span(),
+                format!(
+                    "Unknown key `{}` for `validate`, expected `validator` or `error`",
+                    key
+                ),
+            )),
+        })?;
+        Ok(this)
+    }
+}
+
+/// Field-level `#[opaque_typedef(..)]` attributes.
+#[derive(Default)]
+pub struct FieldAttrs {
+    /// `inner`.
+    inner: Option<Span>,
+    /// `init = ..`.
+    init: Option<Expr>,
+    /// `getter`, `getter = ..`, or `getter(..)`.
+    getter: Option<GetterAttr>,
+}
+
+impl FieldAttrs {
+    /// Parses the field-level attributes.
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut this = Self::default();
+        parse_attrs(attrs, |key, input| this.parse_item(key, input))?;
+        Ok(this)
+    }
+
+    /// Parses an item of the field-level attribute.
+    fn parse_item(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        match key.to_string().as_str() {
+            "inner" => {
+                parse_word(key, input)?;
+                set_once(&mut self.inner, key, key.span())
+            }
+            "init" => {
+                let init = parse_value(key, input, "initializer expression")?;
+                set_once(&mut self.init, key, init)
+            }
+            "getter" => {
+                let getter = GetterAttr::parse(key, input)?;
+                set_once(&mut self.getter, key, getter)
+            }
+            _ => Err(unknown_key_error(key, TYPE_KEYS, "type")),
+        }
+    }
+
+    /// Returns the span of `inner` if the field is marked as inner.
+    pub fn inner(&self) -> Option<Span> {
+        self.inner
+    }
+
+    /// Returns the initializer expression if available.
+    pub fn init(&self) -> Option<&Expr> {
+        self.init.as_ref()
+    }
+
+    /// Returns `getter` if specified.
+    pub fn getter(&self) -> Option<&GetterAttr> {
+        self.getter.as_ref()
+    }
+}
+
+/// `getter`, `getter = ..`, or `getter(..)` in the field-level attribute.
+pub struct GetterAttr {
+    /// Span of the key.
+    span: Span,
+    /// `name = ..` or `getter = ..`.
+    name: Option<Ident>,
+    /// `vis = ..`.
+    vis: Option<Visibility>,
+}
+
+impl GetterAttr {
+    /// Parses `getter`, `getter = name`, or `getter(name = .., vis = ..)`.
+    fn parse(key: &Ident, input: ParseStream) -> syn::Result<Self> {
+        let mut this = Self {
+            span: key.span(),
+            name: None,
+            vis: None,
+        };
+        if input.peek(Token![=]) {
+            this.name = Some(parse_value(key, input, "getter name")?);
+            return Ok(this);
+        }
+        if !input.peek(syn::token::Paren) {
+            parse_word(key, input)?;
+            return Ok(this);
+        }
+        let content = parse_list(key, input)?;
+        parse_items(&content, |key, input| match key.to_string().as_str() {
+            "name" => {
+                let name = parse_value(key, input, "getter name")?;
+                set_once(&mut this.name, key, name)
+            }
+            "vis" => {
+                let vis = parse_value(key, input, "visibility")?;
+                set_once(&mut this.vis, key, vis)
+            }
+            _ => Err(syn::Error::new(
+                key.span(),
+                format!("Unknown key `{}` for `getter`", key),
+            )),
+        })?;
+        Ok(this)
+    }
+
+    /// Returns the span of the key.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns the getter name if specified.
+    pub fn name(&self) -> Option<&Ident> {
+        self.name.as_ref()
+    }
+
+    /// Returns the visibility of the getter if specified.
+    pub fn vis(&self) -> Option<&Visibility> {
+        self.vis.as_ref()
+    }
+}
+
+/// Parses all `#[opaque_typedef(..)]` and `#[opaque_typedef::key]` attributes.
+///
+/// `parse_item` is called with the key and the stream following the key, and it should consume
+/// the value of the item (if exists).
+fn parse_attrs(
+    attrs: &[Attribute],
+    mut parse_item: impl FnMut(&Ident, ParseStream) -> syn::Result<()>,
+) -> syn::Result<()> {
+    let mut errors = Errors::new();
+    for attr in attrs {
+        let segments = &attr.path.segments;
+        if segments.is_empty() || segments[0].ident != ATTR_NAME {
+            continue;
+        }
+        let result = match segments.len() {
+            // `#[opaque_typedef(key, key = value, key(..))]`.
+            1 => attr.parse_args_with(|input: ParseStream| parse_items(input, &mut parse_item)),
+            // `#[opaque_typedef::key]`.
+            // NOTE:
+            // Type level attribute in `#[foo::bar]` syntax is not intended (and not supported)
+            // by Rust 1.37.
+            // See <https://github.com/rust-lang/rust/issues/55168>.
+            2 => {
+                let key = &segments[1].ident;
+                let parser = |input: ParseStream| parse_item(key, input);
+                syn::parse::Parser::parse2(parser, attr.tokens.clone())
+            }
+            _ => Err(syn::Error::new_spanned(
+                &attr.path,
+                format!("Unknown attribute path for `{}`", ATTR_NAME),
+            )),
+        };
+        errors.collect(result);
+    }
+    errors.finish()
+}
+
+/// Parses comma-separated items such as `key`, `key = value`, and `key(..)`.
+fn parse_items(
+    input: ParseStream,
+    mut parse_item: impl FnMut(&Ident, ParseStream) -> syn::Result<()>,
+) -> syn::Result<()> {
+    while !input.is_empty() {
+        let key = input.call(Ident::parse_any)?;
+        parse_item(&key, input)?;
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(())
+}
+
+/// Ensures the item is a word without values.
+fn parse_word(key: &Ident, input: ParseStream) -> syn::Result<()> {
+    if input.is_empty() || input.peek(Token![,]) {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            input.span(),
+            format!("`{}` does not take a value", key),
+        ))
+    }
+}
+
+/// Parses `(..)` following the key, and returns the content.
+fn parse_list<'a>(key: &Ident, input: &ParseBuffer<'a>) -> syn::Result<ParseBuffer<'a>> {
+    if !input.peek(syn::token::Paren) {
+        return Err(syn::Error::new(
+            key.span(),
+            format!("Expected `{}(..)`", key),
+        ));
+    }
+    let content;
+    parenthesized!(content in input);
+    Ok(content)
+}
+
+/// Parses `= value` or `= "value"` following the key.
+///
+/// The string literal form is supported for compatibility, and its content is parsed as `T`.
+/// `what` is the description of the value used in error messages.
+fn parse_value<T: Parse>(key: &Ident, input: ParseStream, what: &str) -> syn::Result<T> {
+    if !input.peek(Token![=]) {
+        return Err(syn::Error::new(
+            key.span(),
+            format!("Expected `{} = ..`", key),
+        ));
+    }
+    input.parse::<Token![=]>()?;
+    if input.peek(Lit) {
+        return match input.parse::<Lit>()? {
+            Lit::Str(lit) => parse_lit_str(&lit, what),
+            lit => Err(syn::Error::new(
+                lit.span(),
+                format!(
+                    "Expected {} or string literal as the value of `{}`",
+                    what, key
+                ),
+            )),
+        };
+    }
+    input.parse()
+}
+
+/// Parses the content of the string literal as `T`.
+///
+/// `what` is the description of the value used in error messages.
+fn parse_lit_str<T: Parse>(lit: &LitStr, what: &str) -> syn::Result<T> {
+    lit.parse()
+        .map_err(|e| syn::Error::new(lit.span(), format!("Failed to parse {}: {}", what, e)))
+}
+
+/// Sets the value to the slot, or returns an error if the slot already has a value.
+fn set_once<T>(slot: &mut Option<T>, key: &Ident, value: T) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(syn::Error::new(
+            key.span(),
+            format!("Duplicate `{}` is specified", key),
+        ));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+/// Creates an error for the unknown key.
+///
+/// `misplaced_keys` are keys available at the other `level` (i.e. "type" or "field").
+fn unknown_key_error(key: &Ident, misplaced_keys: &[&str], level: &str) -> syn::Error {
+    let key_str = key.to_string();
+    let msg = if misplaced_keys.contains(&key_str.as_str()) {
+        format!(
+            "`{}` is only available for {}-level `{}` attribute",
+            key, level, ATTR_NAME
+        )
+    } else {
+        format!("Unknown key `{}` for `{}` attribute", key, ATTR_NAME)
+    };
+    syn::Error::new(key.span(), msg)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_eq_path_components() {
-        let foo: Path = syn::parse_str("foo").unwrap();
-        assert!(eq_path_components(&foo, &["foo"]));
-        assert!(!eq_path_components(&foo, &["bar"]));
+    use quote::{quote, ToTokens};
 
-        let foo_bar: Path = syn::parse_str("foo::bar").unwrap();
-        assert!(eq_path_components(&foo_bar, &["foo", "bar"]));
-        assert!(!eq_path_components(&foo_bar, &["foo"]));
-        assert!(!eq_path_components(&foo_bar, &["bar"]));
-        assert!(!eq_path_components(&foo_bar, &["foo::bar"]));
+    /// Parses the type-level attributes of the given item.
+    fn type_attrs(input: syn::DeriveInput) -> syn::Result<TypeAttrs> {
+        TypeAttrs::from_attrs(&input.attrs)
+    }
+
+    /// Parses the field-level attributes of the first field of the given item.
+    fn field_attrs(input: syn::ItemStruct) -> syn::Result<FieldAttrs> {
+        let field = input.fields.iter().next().expect("Should have a field");
+        FieldAttrs::from_attrs(&field.attrs)
     }
 
     #[test]
@@ -166,15 +1473,617 @@ mod tests {
     }
 
     #[test]
-    fn test_has_level2_word() {
-        let foo_bar_path: Meta = syn::parse_str("foo::bar").unwrap();
-        assert!(foo_bar_path.has_level2_word("foo", "bar"));
+    fn type_attrs_words() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(hide_base_impl_docs)]
+            #[doc = "Irrelevant attribute."]
+            #[opaque_typedef(allow_unchecked_mut, from_str)]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert!(attrs.hide_base_impl_docs());
+        assert!(attrs.allow_unchecked_mut());
+        assert!(attrs.impl_from_str().is_some());
+        assert!(attrs.validator().is_none());
+        assert!(attrs.ty_validation_error().is_none());
+    }
+
+    #[test]
+    fn type_attrs_validate() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validate(validator = "validate", error = "Error"))]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert!(!attrs.hide_base_impl_docs());
+        assert!(attrs.validator().is_some());
+        assert!(attrs.ty_validation_error().is_some());
+    }
+
+    #[test]
+    fn type_attrs_validate_tokens() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validate(validator = validate::<T>, error = Error<T>))]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert_eq!(
+            attrs.validator().into_token_stream().to_string(),
+            quote!(validate::<T>).to_string()
+        );
+        assert_eq!(
+            attrs.ty_validation_error().into_token_stream().to_string(),
+            quote!(Error<T>).to_string()
+        );
+
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validate(
+                validator = |s: &str| if s.is_empty() { Err(Empty) } else { Ok(s) },
+                error = Empty,
+            ))]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert_eq!(
+            attrs.validator().into_token_stream().to_string(),
+            quote!(|s: &str| if s.is_empty() { Err(Empty) } else { Ok(s) }).to_string()
+        );
+    }
+
+    #[test]
+    fn type_attrs_as_ref() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(as_ref(inner, self, "[u8]"), as_mut(inner))]
+            #[opaque_typedef(as_ref(std::path::Path))]
+            struct Foo(T);
+        })
+        .unwrap();
+        let targets = attrs
+            .as_ref_targets()
+            .iter()
+            .map(|target| match target {
+                RefTarget::Inner(_) => "inner".to_owned(),
+                RefTarget::SelfType(_) => "self".to_owned(),
+                RefTarget::Type(ty) => ty.into_token_stream().to_string(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            [
+                "inner".to_owned(),
+                "self".to_owned(),
+                quote!([u8]).to_string(),
+                quote!(std::path::Path).to_string(),
+            ]
+        );
+        assert_eq!(attrs.as_mut_targets().len(), 1);
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(as_ref())]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(as_ref = "str")]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_cmp_with() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(cmp_with(inner, "&str"))]
+            #[opaque_typedef(cmp_with(base = str, String))]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert_eq!(attrs.cmp_with_targets().len(), 3);
+        assert_eq!(
+            attrs.cmp_with_base().into_token_stream().to_string(),
+            quote!(str).to_string()
+        );
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(cmp_with(base = str))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(cmp_with(self))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(cmp_with(base = str, inner), cmp_with(base = str, String))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_fmt() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(fmt(display, lower_hex))]
+            #[opaque_typedef(fmt(debug = "transparent", pointer))]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert_eq!(attrs.fmt().traits(), ["Display", "LowerHex", "Pointer"]);
+        assert_eq!(attrs.fmt().debug(), Some(DebugMode::Transparent));
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(fmt(display), fmt(display))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(fmt(debug = "opaque"))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(fmt(debug))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_ops() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(ops(add, neg), ops(add_assign, on_invalid = "result", mask))]
+            #[opaque_typedef(ops(shl(rhs = "u32")))]
+            struct Foo(T);
+        })
+        .unwrap();
+        let names = attrs
+            .ops()
+            .traits()
+            .iter()
+            .map(|op| op.op().name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Add", "Neg", "AddAssign", "Shl"]);
+        let rhs = attrs.ops().traits()[3].rhs();
+        assert_eq!(
+            rhs.map(|ty| quote!(#ty).to_string()),
+            Some("u32".to_owned())
+        );
+        assert_eq!(
+            attrs.ops().on_invalid().map(|(_, v)| v),
+            Some(OnInvalid::Result)
+        );
+        assert!(attrs.ops().mask());
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(ops(add, add))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(ops(pow))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(ops(on_invalid = "ignore"))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(ops(shl))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(ops(add(rhs = "u32")))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_convert() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(convert(from_inner), convert(into_inner))]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert!(attrs.convert().impl_from_inner());
+        assert!(attrs.convert().impl_into_inner());
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(convert(from_inner, from_inner))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(convert(as_inner))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_smart_ptr() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(smart_ptr(box, rc), smart_ptr(arc))]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert_eq!(
+            attrs.smart_ptr().ptrs(),
+            [SmartPtr::Box, SmartPtr::Rc, SmartPtr::Arc]
+        );
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(smart_ptr(box, box))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(smart_ptr(cow))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_cmp() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(cmp(key = "str::trim"))]
+            struct Foo(T);
+        })
+        .unwrap();
+        match attrs.cmp().unwrap() {
+            CmpKey::Fn(func) => {
+                assert_eq!(
+                    func.to_token_stream().to_string(),
+                    quote!(str::trim).to_string()
+                )
+            }
+            CmpKey::AsciiCaseInsensitive => panic!("Unexpected `ascii_case_insensitive`"),
+        }
+
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(cmp(ascii_case_insensitive))]
+            struct Foo(T);
+        })
+        .unwrap();
+        assert!(matches!(attrs.cmp().unwrap(), CmpKey::AsciiCaseInsensitive));
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(cmp(key = str::trim, ascii_case_insensitive))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(cmp())]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_derive() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(derive(Clone, PartialEq), derive(Hash))]
+            struct Foo(T);
+        })
+        .unwrap();
+        let traits = attrs.derive().traits().collect::<Vec<_>>();
+        assert_eq!(
+            traits,
+            [("Clone", "clone"), ("PartialEq", "cmp"), ("Hash", "hash")]
+        );
+        assert!(attrs.derive().get("Hash").is_some());
+        assert!(attrs.derive().get("Debug").is_none());
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(derive(Clone), derive(Clone))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(derive(Default))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_bound() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(bound = "T: Ord, U: Clone")]
+            #[opaque_typedef(bound(Display = "", Add = "T: Copy"))]
+            struct Foo(T);
+        })
+        .unwrap();
+        let bound = attrs.bound();
+        assert_eq!(bound.common().len(), 2);
+        assert_eq!(
+            bound.common()[0].to_token_stream().to_string(),
+            quote!(T: Ord).to_string()
+        );
+        assert_eq!(bound.for_trait("Display").map(<[_]>::len), Some(0));
+        assert_eq!(bound.for_trait("Add").map(<[_]>::len), Some(1));
+        assert!(bound.for_trait("Sub").is_none());
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(bound = "T: Ord", bound = "T: Eq")]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(bound(Add = "T: Copy", Add = ""))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(bound = T)]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(bound = "T Ord")]
+            struct Foo(T);
+        })
+        .is_err());
+
+        let err = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(bound(Dispaly = "T: Display"))]
+            struct Foo(T);
+        })
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(err.starts_with("Unknown trait `Dispaly`"), "{}", err);
+        assert!(err.contains("`Display`"), "{}", err);
+    }
+
+    #[test]
+    fn type_attrs_pair() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(owned = "MyString")]
+            struct Foo(T);
+        })
+        .unwrap();
+        let pair = attrs.pair().unwrap();
+        assert_eq!(pair.key(), "owned");
+        assert_eq!(
+            pair.ty().into_token_stream().to_string(),
+            quote!(MyString).to_string()
+        );
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(owned = MyString, borrowed = MyStr)]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn type_attrs_deref() {
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(deref(target = "inner"))]
+            struct Foo(T);
+        })
+        .unwrap();
+        let deref = attrs.deref().unwrap();
+        assert!(deref.target().is_none());
+        assert!(deref.mutable().is_none());
+
+        let attrs = type_attrs(syn::parse_quote! {
+            #[opaque_typedef(deref(target = str, mut))]
+            struct Foo(T);
+        })
+        .unwrap();
+        let deref = attrs.deref().unwrap();
+        assert_eq!(
+            deref.target().into_token_stream().to_string(),
+            quote!(str).to_string()
+        );
+        assert!(deref.mutable().is_some());
+
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(deref(target = self))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(deref(traget = str))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validte(validator = "validate"))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(hide_base_impl_doc)]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validate(validater = "validate"))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn duplicate_key() {
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validate(validator = "validate", validator = "validate2"))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(hide_base_impl_docs)]
+            #[opaque_typedef(hide_base_impl_docs)]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validate(validator = "validate"))]
+            #[opaque_typedef(validate(error = "Error"))]
+            struct Foo(T);
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn wrong_value() {
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validate(validator = 42))]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(hide_base_impl_docs = "true")]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(validate = "validate")]
+            struct Foo(T);
+        })
+        .is_err());
+    }
 
-        let foo_bar: Meta = syn::parse_str("foo(dummy, bar, dummy(dummy))").unwrap();
-        assert!(foo_bar.has_level2_word("foo", "bar"));
-        assert!(!foo_bar.has_level2_word("dummy", "dummy"));
+    #[test]
+    fn misplaced_key() {
+        assert!(type_attrs(syn::parse_quote! {
+            #[opaque_typedef(inner)]
+            struct Foo(T);
+        })
+        .is_err());
+        assert!(field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(hide_base_impl_docs)]
+                inner: T,
+            }
+        })
+        .is_err());
+    }
 
-        let foo_bar_baz: Meta = syn::parse_str("foo(dummy, bar(baz), dummy(dummy))").unwrap();
-        assert!(!foo_bar_baz.has_level2_word("foo", "bar"));
+    #[test]
+    fn field_attrs_inner() {
+        let attrs = field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(inner)]
+                inner: T,
+            }
+        })
+        .unwrap();
+        assert!(attrs.inner().is_some());
+
+        let attrs = field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef::inner]
+                inner: T,
+            }
+        })
+        .unwrap();
+        assert!(attrs.inner().is_some());
+
+        let attrs = field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[doc = "Inner data."]
+                inner: T,
+            }
+        })
+        .unwrap();
+        assert!(attrs.inner().is_none());
+    }
+
+    #[test]
+    fn field_attrs_init() {
+        let attrs = field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(init = Tag::new())]
+                tag: Tag,
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            attrs.init().into_token_stream().to_string(),
+            quote!(Tag::new()).to_string()
+        );
+        assert!(attrs.inner().is_none());
+
+        let attrs = field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(init = "Tag::DEFAULT")]
+                tag: Tag,
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            attrs.init().into_token_stream().to_string(),
+            quote!(Tag::DEFAULT).to_string()
+        );
+
+        assert!(field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(init = Tag::new(), init = Tag::new())]
+                tag: Tag,
+            }
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn field_attrs_getter() {
+        let attrs = field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(inner, getter)]
+                start: T,
+            }
+        })
+        .unwrap();
+        let getter = attrs.getter().unwrap();
+        assert!(getter.name().is_none());
+        assert!(getter.vis().is_none());
+
+        let attrs = field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(inner, getter = "start_date")]
+                start: T,
+            }
+        })
+        .unwrap();
+        let name = attrs.getter().and_then(GetterAttr::name);
+        assert_eq!(name.map(ToString::to_string), Some("start_date".to_owned()));
+
+        let attrs = field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(inner, getter(name = start_date, vis = "pub(crate)"))]
+                start: T,
+            }
+        })
+        .unwrap();
+        let getter = attrs.getter().unwrap();
+        assert_eq!(
+            getter.name().map(ToString::to_string),
+            Some("start_date".to_owned())
+        );
+        assert_eq!(
+            getter.vis().into_token_stream().to_string(),
+            quote!(pub(crate)).to_string()
+        );
+
+        assert!(field_attrs(syn::parse_quote! {
+            struct Foo {
+                #[opaque_typedef(getter(visibility = "pub"))]
+                start: T,
+            }
+        })
+        .is_err());
     }
 }
@@ -1,3 +1,70 @@
 //! Codegen for derive.
 
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{error::Errors, input::Input};
+
+pub mod as_ref;
 pub mod base;
+pub mod cmp;
+pub mod cmp_with;
+pub mod convert;
+pub mod deref;
+pub mod fmt;
+pub mod from_str;
+pub mod ops;
+pub mod pair;
+pub mod smart_ptr;
+pub mod std_traits;
+
+/// Kind of the opaque typedef, i.e. which base trait is derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// `OpaqueTypedefSized`.
+    Sized,
+    /// `OpaqueTypedefUnsized`.
+    Unsized,
+}
+
+impl Kind {
+    /// Returns the path of the base trait.
+    pub fn base_trait(self) -> TokenStream {
+        match self {
+            Kind::Sized => quote!(opaque_typedef::OpaqueTypedefSized),
+            Kind::Unsized => quote!(opaque_typedef::OpaqueTypedefUnsized),
+        }
+    }
+
+    /// Returns the path of the trait for mutable access to the inner value.
+    pub fn mut_trait(self) -> TokenStream {
+        match self {
+            Kind::Sized => quote!(opaque_typedef::OpaqueTypedefSizedMut),
+            Kind::Unsized => quote!(opaque_typedef::OpaqueTypedefUnsizedMut),
+        }
+    }
+}
+
+/// Generates impls requested by the type-level attributes.
+///
+/// These impls are generated by the derive of the base trait (`OpaqueTypedefSized` or
+/// `OpaqueTypedefUnsized`).
+pub fn gen_attr_impls(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    let mut errors = Errors::new();
+    let mut toks = TokenStream::new();
+    toks.extend(errors.collect(std_traits::gen_std_traits(input, kind)));
+    toks.extend(as_ref::gen_as_ref(input, kind));
+    toks.extend(errors.collect(as_ref::gen_as_mut(input, kind)));
+    toks.extend(errors.collect(deref::gen_deref(input, kind)));
+    toks.extend(errors.collect(cmp::gen_cmp(input, kind)));
+    toks.extend(cmp_with::gen_cmp_with(input, kind));
+    toks.extend(fmt::gen_fmt(input, kind));
+    toks.extend(errors.collect(ops::gen_ops(input, kind)));
+    toks.extend(errors.collect(from_str::gen_from_str(input, kind)));
+    toks.extend(errors.collect(pair::gen_pair(input, kind)));
+    toks.extend(convert::gen_convert(input, kind));
+    toks.extend(errors.collect(smart_ptr::gen_smart_ptr(input, kind)));
+    errors.finish()?;
+
+    Ok(toks)
+}
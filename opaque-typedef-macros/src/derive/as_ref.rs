@@ -0,0 +1,220 @@
+//! `AsRef` and `AsMut` codegen.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{attr::RefTarget, derive::Kind, input::Input};
+
+/// Generate impls for `AsRef`.
+pub fn gen_as_ref(input: &Input, kind: Kind) -> TokenStream {
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = kind.base_trait();
+    let expr_inner = quote!(<Self as #base_trait>::as_inner(self));
+
+    let impls = input.as_ref_targets().iter().map(|target| {
+        let (_, generics_ty, _) = input.generics().split_for_impl();
+        let (ty_target, expr, predicate) = match target {
+            RefTarget::Inner(_) => (quote!(#ty_inner), expr_inner.clone(), None),
+            RefTarget::SelfType(_) => (quote!(#ty #generics_ty), quote!(self), None),
+            RefTarget::Type(ty_target) => (
+                quote!(#ty_target),
+                quote!(<#ty_inner as std::convert::AsRef<#ty_target>>::as_ref(#expr_inner)),
+                Some(syn::parse_quote!(#ty_inner: std::convert::AsRef<#ty_target>)),
+            ),
+        };
+        let generics = input.generics_for("AsRef", predicate);
+        let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+        quote! {
+            impl #generics_impl std::convert::AsRef<#ty_target> for #ty #generics_ty #generics_where {
+                fn as_ref(&self) -> &#ty_target {
+                    #expr
+                }
+            }
+        }
+    });
+
+    quote!(#(#impls)*)
+}
+
+/// Generate impls for `AsMut`.
+///
+/// `AsMut` to the inner value uses the `*Mut` trait of the kind, so it should also be derived.
+pub fn gen_as_mut(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    let targets = input.as_mut_targets();
+    if let (Some(target), Some(_)) = (targets.first(), input.validator()) {
+        // `AsMut` gives safe mutable access, so it is allowed only when the validation never
+        // fails.
+        return Err(syn::Error::new(
+            target.span(),
+            "`as_mut` is available only for types without validator",
+        ));
+    }
+
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let mut_trait = kind.mut_trait();
+    let expr_inner = quote!(<Self as #mut_trait>::as_inner_mut(self));
+
+    let impls = targets.iter().map(|target| {
+        let (_, generics_ty, _) = input.generics().split_for_impl();
+        let (ty_target, expr, predicate) = match target {
+            RefTarget::Inner(_) => (quote!(#ty_inner), expr_inner.clone(), None),
+            RefTarget::SelfType(_) => (quote!(#ty #generics_ty), quote!(self), None),
+            RefTarget::Type(ty_target) => (
+                quote!(#ty_target),
+                quote!(<#ty_inner as std::convert::AsMut<#ty_target>>::as_mut(#expr_inner)),
+                Some(syn::parse_quote!(#ty_inner: std::convert::AsMut<#ty_target>)),
+            ),
+        };
+        let generics = input.generics_for("AsMut", predicate);
+        let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+        quote! {
+            impl #generics_impl std::convert::AsMut<#ty_target> for #ty #generics_ty #generics_where {
+                fn as_mut(&mut self) -> &mut #ty_target {
+                    #expr
+                }
+            }
+        }
+    });
+
+    Ok(quote!(#(#impls)*))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn as_ref_targets() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(as_ref(inner, self, "[u8]", std::path::Path))]
+            pub struct MyStr(str);
+        };
+        let toks = gen_as_ref(&Input::new(&input).unwrap(), Kind::Unsized);
+        let expected = quote! {
+            impl std::convert::AsRef<str> for MyStr {
+                fn as_ref(&self) -> &str {
+                    <Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner(self)
+                }
+            }
+            impl std::convert::AsRef<MyStr> for MyStr {
+                fn as_ref(&self) -> &MyStr {
+                    self
+                }
+            }
+            impl std::convert::AsRef<[u8]> for MyStr
+            where
+                str: std::convert::AsRef<[u8]>
+            {
+                fn as_ref(&self) -> &[u8] {
+                    <str as std::convert::AsRef<[u8]>>::as_ref(
+                        <Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner(self)
+                    )
+                }
+            }
+            impl std::convert::AsRef<std::path::Path> for MyStr
+            where
+                str: std::convert::AsRef<std::path::Path>
+            {
+                fn as_ref(&self) -> &std::path::Path {
+                    <str as std::convert::AsRef<std::path::Path>>::as_ref(
+                        <Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner(self)
+                    )
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn as_ref_generic() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(as_ref(inner, "[T]"))]
+            pub struct MyVec<T: Clone>(Vec<T>) where T: Default;
+        };
+        let toks = gen_as_ref(&Input::new(&input).unwrap(), Kind::Sized);
+        let expected = quote! {
+            impl<T: Clone> std::convert::AsRef<Vec<T> > for MyVec<T>
+            where
+                T: Default
+            {
+                fn as_ref(&self) -> &Vec<T> {
+                    <Self as opaque_typedef::OpaqueTypedefSized>::as_inner(self)
+                }
+            }
+            impl<T: Clone> std::convert::AsRef<[T]> for MyVec<T>
+            where
+                T: Default,
+                Vec<T>: std::convert::AsRef<[T]>
+            {
+                fn as_ref(&self) -> &[T] {
+                    <Vec<T> as std::convert::AsRef<[T]>>::as_ref(
+                        <Self as opaque_typedef::OpaqueTypedefSized>::as_inner(self)
+                    )
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn as_mut_targets() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+            #[opaque_typedef(as_mut(inner, self, str))]
+            pub struct MyString(String);
+        };
+        let toks = gen_as_mut(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            impl std::convert::AsMut<String> for MyString {
+                fn as_mut(&mut self) -> &mut String {
+                    <Self as opaque_typedef::OpaqueTypedefSizedMut>::as_inner_mut(self)
+                }
+            }
+            impl std::convert::AsMut<MyString> for MyString {
+                fn as_mut(&mut self) -> &mut MyString {
+                    self
+                }
+            }
+            impl std::convert::AsMut<str> for MyString
+            where
+                String: std::convert::AsMut<str>
+            {
+                fn as_mut(&mut self) -> &mut str {
+                    <String as std::convert::AsMut<str>>::as_mut(
+                        <Self as opaque_typedef::OpaqueTypedefSizedMut>::as_inner_mut(self)
+                    )
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn as_mut_with_validator() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+            #[opaque_typedef(as_mut(inner))]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            pub struct MyString(String);
+        };
+        let toks = gen_as_mut(&Input::new(&input).unwrap(), Kind::Sized);
+        assert!(toks.is_err());
+    }
+
+    #[test]
+    fn as_mut_with_validator_allow_unchecked_mut() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+            #[opaque_typedef(as_mut(inner), allow_unchecked_mut)]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            pub struct MyString(String);
+        };
+        let toks = gen_as_mut(&Input::new(&input).unwrap(), Kind::Sized);
+        assert!(toks.is_err());
+    }
+}
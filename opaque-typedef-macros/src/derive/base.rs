@@ -1,13 +1,14 @@
 //! Base traits.
 
 pub use self::{
-    base_sized::gen_base_sized, base_sized_infallible::gen_base_sized_infallible,
-    base_sized_mut::gen_base_sized_mut, base_unsized::gen_base_unsized,
-    base_unsized_infallible::gen_base_unsized_infallible,
+    base_composite::gen_base_composite, base_sized::gen_base_sized,
+    base_sized_infallible::gen_base_sized_infallible, base_sized_mut::gen_base_sized_mut,
+    base_unsized::gen_base_unsized, base_unsized_infallible::gen_base_unsized_infallible,
     base_unsized_infallible_mut::gen_base_unsized_infallible_mut,
     base_unsized_mut::gen_base_unsized_mut,
 };
 
+pub mod base_composite;
 pub mod base_sized;
 pub mod base_sized_infallible;
 pub mod base_sized_mut;
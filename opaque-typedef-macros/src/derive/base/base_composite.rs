@@ -0,0 +1,209 @@
+//! `OpaqueTypedefComposite` codegen.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::input::{FieldAccessor, Input};
+
+/// Generate impl for `OpaqueTypedefComposite`, and getters for the inner fields.
+///
+/// Getters are generated only for the fields with `getter`, because they can expose private
+/// fields and clash with the methods defined by users.
+/// They are private by default, and named after the fields unless the name is specified.
+pub fn gen_base_composite(input: &Input) -> TokenStream {
+    let ty = input.ident();
+    let generics = input.generics_for("OpaqueTypedefComposite", None);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+    let fields = input.primary_fields().collect::<Vec<_>>();
+    let tys_inner = fields.iter().map(|field| field.ty());
+    let accessors = fields
+        .iter()
+        .map(|field| field.accessor())
+        .collect::<Vec<_>>();
+    let ty_error = input.ty_error_force();
+    let validate = input.validator().map(|validator| {
+        quote! {
+            let __inner = (#validator)(__inner)?;
+        }
+    });
+    let exprs_inner = (0..fields.len())
+        .map(|i| {
+            let index = syn::Index::from(i);
+            quote!(__inner.#index)
+        })
+        .collect::<Vec<_>>();
+    let expr_from_inner = input.construct_composite(exprs_inner);
+    let base_impl_attrs = input.base_impl_attrs();
+
+    let getters = fields
+        .iter()
+        .filter_map(|field| {
+            let getter = field.getter()?;
+            let ident = match field.accessor() {
+                FieldAccessor::Named(ident) => ident,
+                FieldAccessor::Unnamed(_) => unreachable!("Should be rejected on parsing"),
+            };
+            let name = getter.name().unwrap_or(ident);
+            let vis = getter.vis();
+            let ty_field = field.ty();
+            let doc = format!("Returns a reference to `{}`.", ident);
+            Some(quote! {
+                #[doc = #doc]
+                #vis fn #name(&self) -> &#ty_field {
+                    &self.#ident
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    let impl_getters = if getters.is_empty() {
+        None
+    } else {
+        let generics = input.generics_for_inherent(None);
+        let (generics_impl, _, generics_where) = generics.split_for_impl();
+        Some(quote! {
+            impl #generics_impl #ty #generics_ty #generics_where {
+                #(#getters)*
+            }
+        })
+    };
+
+    quote! {
+        #base_impl_attrs
+        impl #generics_impl opaque_typedef::OpaqueTypedefComposite for #ty #generics_ty #generics_where {
+            type Inner = (#(#tys_inner,)*);
+            type Error = #ty_error;
+
+            fn try_from_inner(__inner: Self::Inner) -> Result<Self, Self::Error> {
+                #validate
+                Ok(#expr_from_inner)
+            }
+
+            unsafe fn from_inner_unchecked(__inner: Self::Inner) -> Self {
+                #expr_from_inner
+            }
+
+            fn into_inner(self) -> Self::Inner {
+                (#(self.#accessors,)*)
+            }
+        }
+
+        #impl_getters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_range() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            #[opaque_typedef(validate(error = Error, validator = validate_range))]
+            pub struct DateRange<T> {
+                #[opaque_typedef(inner, getter(vis = "pub"))]
+                start: T,
+                #[opaque_typedef(inner, getter)]
+                end: T,
+                tag: PhantomData<T>,
+            }
+        };
+        let toks = gen_base_composite(&Input::new_composite(&input).unwrap());
+        let expected = quote! {
+            impl<T> opaque_typedef::OpaqueTypedefComposite for DateRange<T> {
+                type Inner = (T, T,);
+                type Error = Error;
+
+                fn try_from_inner(__inner: Self::Inner) -> Result<Self, Self::Error> {
+                    let __inner = (validate_range)(__inner)?;
+                    Ok(Self {
+                        start: __inner.0,
+                        end: __inner.1,
+                        tag: std::marker::PhantomData,
+                    })
+                }
+
+                unsafe fn from_inner_unchecked(__inner: Self::Inner) -> Self {
+                    Self {
+                        start: __inner.0,
+                        end: __inner.1,
+                        tag: std::marker::PhantomData,
+                    }
+                }
+
+                fn into_inner(self) -> Self::Inner {
+                    (self.start, self.end,)
+                }
+            }
+
+            impl<T> DateRange<T> {
+                #[doc = "Returns a reference to `start`."]
+                pub fn start(&self) -> &T {
+                    &self.start
+                }
+                #[doc = "Returns a reference to `end`."]
+                fn end(&self) -> &T {
+                    &self.end
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn single_field() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            struct Wrapper {
+                #[opaque_typedef(inner)]
+                value: i32,
+            }
+        };
+        let toks = gen_base_composite(&Input::new_composite(&input).unwrap());
+        let expected = quote! {
+            impl opaque_typedef::OpaqueTypedefComposite for Wrapper {
+                type Inner = (i32,);
+                type Error = std::convert::Infallible;
+
+                fn try_from_inner(__inner: Self::Inner) -> Result<Self, Self::Error> {
+                    Ok(Self { value: __inner.0, })
+                }
+
+                unsafe fn from_inner_unchecked(__inner: Self::Inner) -> Self {
+                    Self { value: __inner.0, }
+                }
+
+                fn into_inner(self) -> Self::Inner {
+                    (self.value,)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn getter_rename() {
+        // The getter is renamed to avoid clashing with the user-defined `start()`.
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            pub struct DateRange {
+                #[opaque_typedef(inner, getter(name = start_date, vis = "pub(crate)"))]
+                start: Date,
+                #[opaque_typedef(inner)]
+                end: Date,
+            }
+        };
+        let toks = gen_base_composite(&Input::new_composite(&input).unwrap());
+        let expected = quote! {
+            impl DateRange {
+                #[doc = "Returns a reference to `start`."]
+                pub(crate) fn start_date(&self) -> &Date {
+                    &self.start
+                }
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.ends_with(&expected.to_string()), "{}", toks);
+        assert_eq!(toks.matches("fn").count(), 3 + 1);
+    }
+}
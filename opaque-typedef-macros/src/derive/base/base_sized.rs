@@ -8,7 +8,8 @@ use crate::input::Input;
 /// Generate impl for `OpaqueTypedefSized`.
 pub fn gen_base_sized(input: &Input) -> TokenStream {
     let ty = input.ident();
-    let (generics_impl, generics_ty, generics_where) = input.generics().split_for_impl();
+    let generics = input.generics_for("OpaqueTypedefSized", None);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
     let ty_inner = input.primary_field().ty();
     let ty_error = input.ty_error_force();
     let primary_field_accessor = input.primary_field().accessor();
@@ -16,32 +17,8 @@ pub fn gen_base_sized(input: &Input) -> TokenStream {
         || quote!(__inner),
         |validator| quote!((#validator)(__inner)?),
     );
-    let expr_try_from_inner = {
-        let init_fields = input.fields_with_primary_flag().map(|(is_primary, field)| {
-            let accessor = field.accessor();
-            if is_primary {
-                quote!(#accessor: #inner_validated)
-            } else {
-                quote!(#accessor: std::default::Default::default())
-            }
-        });
-        quote!(Ok(Self {
-            #(#init_fields,)*
-        }))
-    };
-    let expr_from_inner_unchecked = {
-        let init_fields = input.fields_with_primary_flag().map(|(is_primary, field)| {
-            let accessor = field.accessor();
-            if is_primary {
-                quote!(#accessor: __inner)
-            } else {
-                quote!(#accessor: std::default::Default::default())
-            }
-        });
-        quote!(Self {
-            #(#init_fields,)*
-        })
-    };
+    let expr_try_from_inner = input.construct(inner_validated);
+    let expr_from_inner_unchecked = input.construct(quote!(__inner));
     let base_impl_attrs = input.base_impl_attrs();
 
     quote! {
@@ -51,7 +28,7 @@ pub fn gen_base_sized(input: &Input) -> TokenStream {
             type Error = #ty_error;
 
             fn try_from_inner(__inner: Self::Inner) -> Result<Self, Self::Error> {
-                #expr_try_from_inner
+                Ok(#expr_try_from_inner)
             }
 
             unsafe fn from_inner_unchecked(__inner: Self::Inner) -> Self {
@@ -17,20 +17,9 @@ pub fn gen_base_sized_infallible(input: &Input) -> syn::Result<TokenStream> {
     }
 
     let ty = input.ident();
-    let (generics_impl, generics_ty, generics_where) = input.generics().split_for_impl();
-    let expr_from_inner = {
-        let init_fields = input.fields_with_primary_flag().map(|(is_primary, field)| {
-            let accessor = field.accessor();
-            if is_primary {
-                quote!(#accessor: __inner)
-            } else {
-                quote!(#accessor: std::default::Default::default())
-            }
-        });
-        quote!(Self {
-            #(#init_fields,)*
-        })
-    };
+    let generics = input.generics_for("OpaqueTypedefSizedInfallible", None);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+    let expr_from_inner = input.construct(quote!(__inner));
     let base_impl_attrs = input.base_impl_attrs();
 
     Ok(quote! {
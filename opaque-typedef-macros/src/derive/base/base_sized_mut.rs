@@ -6,19 +6,24 @@ use quote::quote;
 use crate::input::Input;
 
 /// Generate impl for `OpaqueTypedefSizedMut`.
-pub fn gen_base_sized_mut(input: &Input) -> TokenStream {
+///
+/// The safe `as_inner_mut()` is provided by the trait only for types without validation.
+pub fn gen_base_sized_mut(input: &Input) -> syn::Result<TokenStream> {
+    input.check_unchecked_mut("OpaqueTypedefSizedMut")?;
+
     let ty = input.ident();
-    let (generics_impl, generics_ty, generics_where) = input.generics().split_for_impl();
+    let generics = input.generics_for("OpaqueTypedefSizedMut", None);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
     let primary_field_accessor = input.primary_field().accessor();
     let base_impl_attrs = input.base_impl_attrs();
-    quote! {
+    Ok(quote! {
         #base_impl_attrs
         impl #generics_impl opaque_typedef::OpaqueTypedefSizedMut for #ty #generics_ty #generics_where {
-            fn as_inner_mut(&mut self) -> &mut Self::Inner {
+            unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                 &mut self.#primary_field_accessor
             }
         }
-    }
+    })
 }
 
 #[cfg(test)]
@@ -31,10 +36,10 @@ mod tests {
             #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
             pub struct Simple<T>(pub T);
         };
-        let toks = gen_base_sized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_sized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T> opaque_typedef::OpaqueTypedefSizedMut for Simple<T> {
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.0
                 }
             }
@@ -51,10 +56,10 @@ mod tests {
                 inner: T,
             }
         };
-        let toks = gen_base_sized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_sized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T> opaque_typedef::OpaqueTypedefSizedMut for Simple<T> {
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.inner
                 }
             }
@@ -68,10 +73,10 @@ mod tests {
             #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
             pub struct Simple<T: Clone>(pub T);
         };
-        let toks = gen_base_sized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_sized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T: Clone> opaque_typedef::OpaqueTypedefSizedMut for Simple<T> {
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.0
                 }
             }
@@ -91,10 +96,10 @@ mod tests {
                 tag: Tag,
             }
         };
-        let toks = gen_base_sized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_sized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T, Tag> opaque_typedef::OpaqueTypedefSizedMut for Tagged<T, Tag> {
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.inner
                 }
             }
@@ -108,10 +113,10 @@ mod tests {
             #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
             pub struct Simple<T>(#[opaque_typedef(inner)] pub T);
         };
-        let toks = gen_base_sized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_sized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T> opaque_typedef::OpaqueTypedefSizedMut for Simple<T> {
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.0
                 }
             }
@@ -126,15 +131,38 @@ mod tests {
             #[opaque_typedef(hide_base_impl_docs)]
             pub struct Simple<T>(pub T);
         };
-        let toks = gen_base_sized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_sized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             #[doc(hidden)]
             impl<T> opaque_typedef::OpaqueTypedefSizedMut for Simple<T> {
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.0
                 }
             }
         };
         assert_eq!(toks.to_string(), expected.to_string());
     }
+
+    #[test]
+    fn with_validator() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+            #[opaque_typedef(validate(error = "Error", validator = "validate"))]
+            pub struct Simple<T>(pub T);
+        };
+        let toks = gen_base_sized_mut(&Input::new(&input).unwrap());
+        assert!(toks.is_err());
+    }
+
+    #[test]
+    fn with_validator_allow_unchecked_mut() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+            #[opaque_typedef(allow_unchecked_mut)]
+            #[opaque_typedef(validate(error = "Error", validator = "validate"))]
+            pub struct Simple<T>(pub T);
+        };
+        let toks = gen_base_sized_mut(&Input::new(&input).unwrap());
+        assert!(toks.is_ok());
+    }
 }
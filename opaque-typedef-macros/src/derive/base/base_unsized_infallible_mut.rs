@@ -4,33 +4,29 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::spanned::Spanned;
 
-use crate::input::Input;
+use crate::{error::Errors, input::Input};
 
 /// Generate impl for `OpaqueTypedefUnsizedInfallibleMut`.
 pub fn gen_base_unsized_infallible_mut(input: &Input) -> syn::Result<TokenStream> {
+    let mut errors = Errors::new();
     if let Some(validator) = input.validator() {
         // A validator is specified and it may fail.
-        return Err(syn::Error::new(
+        errors.push(syn::Error::new(
             validator.span(),
             "Validator and `OpaqueTypedefUnsizedInfallibleMut` cannot be specified at the same time",
         ));
     }
-    input.ensure_acceptable_unsized_repr_or_panic();
+    errors.collect(input.ensure_acceptable_unsized_repr());
+    errors.finish()?;
 
     let ty = input.ident();
-    let (generics_impl, generics_ty, generics_where) = input.generics().split_for_impl();
-    // Safety condition of this `unsafe` is same as that of `base_unsized_infallible()`.
-    // Note that using the resulting expression is always safe.
-    let expr_from_inner = quote!(unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) });
+    let generics = input.generics_for("OpaqueTypedefUnsizedInfallibleMut", None);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
     let base_impl_attrs = input.base_impl_attrs();
 
     Ok(quote! {
         #base_impl_attrs
-        impl #generics_impl opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for #ty #generics_ty #generics_where {
-            fn from_inner_mut(__inner: &mut Self::Inner) -> &mut Self {
-                #expr_from_inner
-            }
-        }
+        impl #generics_impl opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for #ty #generics_ty #generics_where {}
     })
 }
 
@@ -53,11 +49,7 @@ mod tests {
             };
             let toks = gen_base_unsized_infallible_mut(&Input::new(&input).unwrap()).unwrap();
             let expected = quote! {
-                impl<T> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {
-                    fn from_inner_mut(__inner: &mut Self::Inner) -> &mut Self {
-                        unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) }
-                    }
-                }
+                impl<T> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {}
             };
             assert_eq!(toks.to_string(), expected.to_string());
         }
@@ -80,11 +72,7 @@ mod tests {
         };
         let toks = gen_base_unsized_infallible_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
-            impl<T> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {
-                fn from_inner_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) }
-                }
-            }
+            impl<T> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {}
         };
         assert_eq!(toks.to_string(), expected.to_string());
     }
@@ -103,11 +91,7 @@ mod tests {
         };
         let toks = gen_base_unsized_infallible_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
-            impl<T: Debug> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {
-                fn from_inner_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) }
-                }
-            }
+            impl<T: Debug> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {}
         };
         assert_eq!(toks.to_string(), expected.to_string());
     }
@@ -132,11 +116,7 @@ mod tests {
         };
         let toks = gen_base_unsized_infallible_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
-            impl<T, Tag> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Tagged<T, Tag> {
-                fn from_inner_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) }
-                }
-            }
+            impl<T, Tag> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Tagged<T, Tag> {}
         };
         assert_eq!(toks.to_string(), expected.to_string());
     }
@@ -155,11 +135,7 @@ mod tests {
         };
         let toks = gen_base_unsized_infallible_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
-            impl<T> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {
-                fn from_inner_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) }
-                }
-            }
+            impl<T> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {}
         };
         assert_eq!(toks.to_string(), expected.to_string());
     }
@@ -180,18 +156,13 @@ mod tests {
         let toks = gen_base_unsized_infallible_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             #[doc(hidden)]
-            impl<T> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {
-                fn from_inner_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) }
-                }
-            }
+            impl<T> opaque_typedef::OpaqueTypedefUnsizedInfallibleMut for Simple<T> {}
         };
         assert_eq!(toks.to_string(), expected.to_string());
     }
 
     /// Types without `#[repr(C)]` and `#[repr(transparent)]` should be rejected.
     #[test]
-    #[should_panic]
     fn no_repr() {
         let input = syn::parse_quote! {
             #[derive(
@@ -202,7 +173,8 @@ mod tests {
             )]
             struct MyStr(str);
         };
-        let _ = gen_base_unsized_infallible_mut(&Input::new(&input).unwrap()).unwrap();
+        let toks = gen_base_unsized_infallible_mut(&Input::new(&input).unwrap());
+        assert!(toks.is_err());
     }
 
     #[test]
@@ -3,38 +3,31 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use crate::input::Input;
+use crate::{error::Errors, input::Input};
 
 /// Generate impl for `OpaqueTypedefUnsizedMut`.
-pub fn gen_base_unsized_mut(input: &Input) -> TokenStream {
-    input.ensure_acceptable_unsized_repr_or_panic();
+///
+/// The safe `as_inner_mut()` is provided by the trait only for types without validation.
+pub fn gen_base_unsized_mut(input: &Input) -> syn::Result<TokenStream> {
+    let mut errors = Errors::new();
+    errors.collect(input.check_unchecked_mut("OpaqueTypedefUnsizedMut"));
+    errors.collect(input.ensure_acceptable_unsized_repr());
+    errors.finish()?;
+
     let ty = input.ident();
-    let (generics_impl, generics_ty, generics_where) = input.generics().split_for_impl();
+    let generics = input.generics_for("OpaqueTypedefUnsizedMut", None);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
     let primary_field_accessor = input.primary_field().accessor();
-    let expr_from_inner_unchecked = quote!(&mut *(__inner as *mut Self::Inner as *mut Self));
-    // Safety condition of this `unsafe` is same as that of `base_unsized()`.
-    // Note that using the resulting expression is NOT always safe.
-    // i.e. unrestricted modification to the inner field may make the value internally inconsistent
-    // and result in undefined behaivor.
-    let expr_from_inner = quote!(unsafe { #expr_from_inner_unchecked });
     let base_impl_attrs = input.base_impl_attrs();
 
-    quote! {
+    Ok(quote! {
         #base_impl_attrs
         impl #generics_impl opaque_typedef::OpaqueTypedefUnsizedMut for #ty #generics_ty #generics_where {
-            fn try_from_inner_mut(__inner: &mut Self::Inner) -> Result<&mut Self, Self::Error> {
-                Ok(#expr_from_inner)
-            }
-
-            unsafe fn from_inner_unchecked_mut(__inner: &mut Self::Inner) -> &mut Self {
-                #expr_from_inner_unchecked
-            }
-
-            fn as_inner_mut(&mut self) -> &mut Self::Inner {
+            unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                 &mut self.#primary_field_accessor
             }
         }
-    }
+    })
 }
 
 #[cfg(test)]
@@ -49,16 +42,10 @@ mod tests {
                 #[repr(#repr)]
                 pub struct Simple<T>(T);
             };
-            let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+            let toks = gen_base_unsized_mut(&Input::new(&input).unwrap()).unwrap();
             let expected = quote! {
                 impl<T> opaque_typedef::OpaqueTypedefUnsizedMut for Simple<T> {
-                    fn try_from_inner_mut(__inner: &mut Self::Inner) -> Result<&mut Self, Self::Error> {
-                        Ok(unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) })
-                    }
-                    unsafe fn from_inner_unchecked_mut(__inner: &mut Self::Inner) -> &mut Self {
-                        &mut *(__inner as *mut Self::Inner as *mut Self)
-                    }
-                    fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                    unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                         &mut self.0
                     }
                 }
@@ -77,16 +64,10 @@ mod tests {
                 inner: T,
             }
         };
-        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T> opaque_typedef::OpaqueTypedefUnsizedMut for Simple<T> {
-                fn try_from_inner_mut(__inner: &mut Self::Inner) -> Result<&mut Self, Self::Error> {
-                    Ok(unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) })
-                }
-                unsafe fn from_inner_unchecked_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    &mut *(__inner as *mut Self::Inner as *mut Self)
-                }
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.inner
                 }
             }
@@ -101,16 +82,10 @@ mod tests {
             #[repr(transparent)]
             pub struct Simple<T: Debug>(T);
         };
-        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T: Debug> opaque_typedef::OpaqueTypedefUnsizedMut for Simple<T> {
-                fn try_from_inner_mut(__inner: &mut Self::Inner) -> Result<&mut Self, Self::Error> {
-                    Ok(unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) })
-                }
-                unsafe fn from_inner_unchecked_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    &mut *(__inner as *mut Self::Inner as *mut Self)
-                }
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.0
                 }
             }
@@ -131,16 +106,10 @@ mod tests {
                 tag: Tag,
             }
         };
-        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T, Tag> opaque_typedef::OpaqueTypedefUnsizedMut for Tagged<T, Tag> {
-                fn try_from_inner_mut(__inner: &mut Self::Inner) -> Result<&mut Self, Self::Error> {
-                    Ok(unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) })
-                }
-                unsafe fn from_inner_unchecked_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    &mut *(__inner as *mut Self::Inner as *mut Self)
-                }
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.inner
                 }
             }
@@ -155,16 +124,10 @@ mod tests {
             #[repr(transparent)]
             pub struct Simple<T>(#[opaque_typedef(inner)] T);
         };
-        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             impl<T> opaque_typedef::OpaqueTypedefUnsizedMut for Simple<T> {
-                fn try_from_inner_mut(__inner: &mut Self::Inner) -> Result<&mut Self, Self::Error> {
-                    Ok(unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) })
-                }
-                unsafe fn from_inner_unchecked_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    &mut *(__inner as *mut Self::Inner as *mut Self)
-                }
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.0
                 }
             }
@@ -180,17 +143,34 @@ mod tests {
             #[opaque_typedef(hide_base_impl_docs)]
             pub struct Simple<T>(pub T);
         };
-        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap()).unwrap();
         let expected = quote! {
             #[doc(hidden)]
             impl<T> opaque_typedef::OpaqueTypedefUnsizedMut for Simple<T> {
-                fn try_from_inner_mut(__inner: &mut Self::Inner) -> Result<&mut Self, Self::Error> {
-                    Ok(unsafe { &mut *(__inner as *mut Self::Inner as *mut Self) })
-                }
-                unsafe fn from_inner_unchecked_mut(__inner: &mut Self::Inner) -> &mut Self {
-                    &mut *(__inner as *mut Self::Inner as *mut Self)
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
+                    &mut self.0
                 }
-                fn as_inner_mut(&mut self) -> &mut Self::Inner {
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn my_str_validation() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut)]
+            #[repr(transparent)]
+            #[opaque_typedef(allow_unchecked_mut)]
+            #[opaque_typedef(validate(
+                validator = "|s| std::str::from_utf8(s).map(|_| s)",
+                error = "std::string::Utf8Error",
+            ))]
+            pub struct MyStr([u8]);
+        };
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap()).unwrap();
+        let expected = quote! {
+            impl opaque_typedef::OpaqueTypedefUnsizedMut for MyStr {
+                unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner {
                     &mut self.0
                 }
             }
@@ -198,14 +178,39 @@ mod tests {
         assert_eq!(toks.to_string(), expected.to_string());
     }
 
+    #[test]
+    fn with_validator() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut)]
+            #[repr(transparent)]
+            #[opaque_typedef(validate(error = "Error", validator = "validate"))]
+            pub struct Simple<T>(pub [T]);
+        };
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        assert!(toks.is_err());
+    }
+
+    #[test]
+    fn with_validator_allow_unchecked_mut() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut)]
+            #[repr(transparent)]
+            #[opaque_typedef(allow_unchecked_mut)]
+            #[opaque_typedef(validate(error = "Error", validator = "validate"))]
+            pub struct Simple<T>(pub [T]);
+        };
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        assert!(toks.is_ok());
+    }
+
     /// Types without `#[repr(C)]` and `#[repr(transparent)]` should be rejected.
     #[test]
-    #[should_panic]
     fn no_repr() {
         let input = syn::parse_quote! {
             #[derive(OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut)]
             struct MyStr(str);
         };
-        let _ = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        let toks = gen_base_unsized_mut(&Input::new(&input).unwrap());
+        assert!(toks.is_err());
     }
 }
@@ -0,0 +1,194 @@
+//! `PartialEq`, `Eq`, `PartialOrd`, `Ord`, and `Hash` codegen by the comparison key.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Ident;
+
+use crate::{attr::CmpKey, derive::Kind, input::Input};
+
+/// Generate impls for `PartialEq`, `Eq`, `PartialOrd`, `Ord`, and `Hash`.
+///
+/// All traits compare or hash the key computed from the inner value, so they are consistent
+/// with each other.
+pub fn gen_cmp(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    let cmp_key = match input.cmp() {
+        Some(v) => v,
+        None => return Ok(TokenStream::new()),
+    };
+    ensure_no_raw_cmp(input)?;
+    let ty = input.ident();
+    let base_trait = kind.base_trait();
+    let key_fn = match cmp_key {
+        CmpKey::Fn(func) => quote!((#func)),
+        CmpKey::AsciiCaseInsensitive => quote!(opaque_typedef::AsciiCaseInsensitive::new),
+    };
+    let key = |expr: TokenStream| quote!(#key_fn(<Self as #base_trait>::as_inner(#expr)));
+    let (key_self, key_other) = (key(quote!(self)), key(quote!(other)));
+    let impl_trait = |module: &str, name: &str, items: TokenStream| {
+        let generics = input.generics_for(name, None);
+        let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+        let trait_ = Ident::new(name, Span::call_site());
+        let module = Ident::new(module, Span::call_site());
+        quote! {
+            impl #generics_impl std::#module::#trait_ for #ty #generics_ty #generics_where {
+                #items
+            }
+        }
+    };
+
+    let impls = vec![
+        impl_trait(
+            "cmp",
+            "PartialEq",
+            quote! {
+                fn eq(&self, other: &Self) -> bool {
+                    #key_self == #key_other
+                }
+            },
+        ),
+        impl_trait("cmp", "Eq", quote!()),
+        impl_trait(
+            "cmp",
+            "PartialOrd",
+            quote! {
+                fn partial_cmp(&self, other: &Self) -> std::option::Option<std::cmp::Ordering> {
+                    std::option::Option::Some(std::cmp::Ord::cmp(self, other))
+                }
+            },
+        ),
+        impl_trait(
+            "cmp",
+            "Ord",
+            quote! {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    std::cmp::Ord::cmp(&#key_self, &#key_other)
+                }
+            },
+        ),
+        impl_trait(
+            "hash",
+            "Hash",
+            quote! {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    std::hash::Hash::hash(&#key_self, state)
+                }
+            },
+        ),
+    ];
+
+    Ok(quote!(#(#impls)*))
+}
+
+/// Ensures no other attributes generate comparisons of the raw inner values.
+///
+/// `cmp_with(..)`, `owned = ..`, and `borrowed = ..` compare the inner values without the key,
+/// which would be inconsistent with the comparisons by `cmp(..)`.
+/// For owned and borrowed types, this would also break the contract of `Borrow`.
+fn ensure_no_raw_cmp(input: &Input) -> syn::Result<()> {
+    if let Some(target) = input.cmp_with_targets().first() {
+        return Err(syn::Error::new(
+            target.span(),
+            "`cmp_with(..)` cannot be specified with `cmp(..)`, \
+             because it compares the inner values without the key",
+        ));
+    }
+    if let Some(pair) = input.pair() {
+        return Err(syn::Error::new(
+            pair.key().span(),
+            format!(
+                "`{}` cannot be specified with `cmp(..)`, \
+                 because it compares the inner values without the key",
+                pair.key()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_fn() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(cmp(key = "str::trim"))]
+            pub struct MyStr(str);
+        };
+        let toks = gen_cmp(&Input::new(&input).unwrap(), Kind::Unsized).unwrap();
+        let key = |expr| quote!((str::trim)(<Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner(#expr)));
+        let (key_self, key_other) = (key(quote!(self)), key(quote!(other)));
+        let expected = quote! {
+            impl std::cmp::PartialEq for MyStr {
+                fn eq(&self, other: &Self) -> bool {
+                    #key_self == #key_other
+                }
+            }
+
+            impl std::cmp::Eq for MyStr {}
+
+            impl std::cmp::PartialOrd for MyStr {
+                fn partial_cmp(&self, other: &Self) -> std::option::Option<std::cmp::Ordering> {
+                    std::option::Option::Some(std::cmp::Ord::cmp(self, other))
+                }
+            }
+
+            impl std::cmp::Ord for MyStr {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    std::cmp::Ord::cmp(&#key_self, &#key_other)
+                }
+            }
+
+            impl std::hash::Hash for MyStr {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    std::hash::Hash::hash(&#key_self, state)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn ascii_case_insensitive() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(cmp(ascii_case_insensitive))]
+            pub struct MyString(String);
+        };
+        let toks = gen_cmp(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            impl std::hash::Hash for MyString {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    std::hash::Hash::hash(
+                        &opaque_typedef::AsciiCaseInsensitive::new(
+                            <Self as opaque_typedef::OpaqueTypedefSized>::as_inner(self)
+                        ),
+                        state
+                    )
+                }
+            }
+        };
+        assert!(toks.to_string().ends_with(&expected.to_string()));
+    }
+
+    #[test]
+    fn with_raw_cmp() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(cmp(ascii_case_insensitive), cmp_with(inner))]
+            pub struct HeaderName(str);
+        };
+        assert!(gen_cmp(&Input::new(&input).unwrap(), Kind::Unsized).is_err());
+
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(cmp(ascii_case_insensitive), owned = "HeaderNameBuf")]
+            pub struct HeaderName(str);
+        };
+        assert!(gen_cmp(&Input::new(&input).unwrap(), Kind::Unsized).is_err());
+    }
+}
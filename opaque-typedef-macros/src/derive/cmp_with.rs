@@ -0,0 +1,278 @@
+//! `PartialEq` and `PartialOrd` codegen for comparison with other types.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Type;
+
+use crate::{attr::RefTarget, derive::Kind, input::Input};
+
+/// Generate impls for `PartialEq` and `PartialOrd` between the type and the `cmp_with` targets.
+///
+/// Both operands are converted into references to the base type (the inner type by default),
+/// and then compared.
+/// For each target `T`, impls for `Outer` and `T` in both directions are generated.
+/// Impls for `&Outer` and `T` are also generated unless `T` is a reference type, because
+/// comparison between references is provided by std.
+pub fn gen_cmp_with(input: &Input, kind: Kind) -> TokenStream {
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let ty_base = input.cmp_with_base();
+    let base_trait = kind.base_trait();
+    let (_, generics_ty, _) = input.generics().split_for_impl();
+    let ty_outer = quote!(#ty #generics_ty);
+
+    // NOTE: Bounds for the conversions are not added to the where clause, because the target
+    // types may have elided lifetimes such as `Cow<'_, str>`, which are not allowed there.
+    let to_base = |ty_from: &dyn quote::ToTokens, expr: TokenStream| match ty_base {
+        Some(ty_base) => quote!(<#ty_from as std::convert::AsRef<#ty_base>>::as_ref(#expr)),
+        None => expr,
+    };
+    let outer_to_base = |expr: TokenStream| {
+        to_base(
+            ty_inner,
+            quote!(<#ty_outer as #base_trait>::as_inner(#expr)),
+        )
+    };
+    let ty_base = match ty_base {
+        Some(ty_base) => quote!(#ty_base),
+        None => quote!(#ty_inner),
+    };
+
+    let impls = input.cmp_with_targets().iter().flat_map(|target| {
+        let (ty_target, target_to_base) = match target {
+            RefTarget::Inner(_) => (quote!(#ty_inner), to_base(ty_inner, quote!(target))),
+            RefTarget::Type(ty_target) => (
+                quote!(#ty_target),
+                quote!(<#ty_target as std::convert::AsRef<#ty_base>>::as_ref(target)),
+            ),
+            RefTarget::SelfType(_) => unreachable!("Should be rejected on parsing"),
+        };
+        let is_ref_target = match target {
+            RefTarget::Type(ty_target) => matches!(**ty_target, Type::Reference(_)),
+            _ => false,
+        };
+
+        let mut toks = gen_cmp_pair(
+            (&ty_outer, &outer_to_base(quote!(outer))),
+            (&ty_target, &target_to_base),
+            &ty_base,
+        );
+        if !is_ref_target {
+            toks.extend(gen_cmp_pair(
+                (&quote!(&'_ #ty_outer), &outer_to_base(quote!(*outer))),
+                (&ty_target, &target_to_base),
+                &ty_base,
+            ));
+        }
+        toks.into_iter().map(
+            |CmpImpl {
+                 trait_name,
+                 ty_trait,
+                 ty_self,
+                 body,
+                 ..
+             }| {
+                let generics = input.generics_for(trait_name, None);
+                let (generics_impl, _, generics_where) = generics.split_for_impl();
+                quote! {
+                    impl #generics_impl #ty_trait for #ty_self #generics_where {
+                        #body
+                    }
+                }
+            },
+        )
+    });
+
+    quote!(#(#impls)*)
+}
+
+/// Comparison impl.
+pub struct CmpImpl {
+    /// Name of the comparison trait, i.e. `"PartialEq"` or `"PartialOrd"`.
+    pub trait_name: &'static str,
+    /// Comparison trait without type parameters, i.e. `PartialEq` or `PartialOrd`.
+    pub trait_: TokenStream,
+    /// Comparison trait with the type parameter.
+    pub ty_trait: TokenStream,
+    /// Self type.
+    pub ty_self: TokenStream,
+    /// Items in the impl.
+    pub body: TokenStream,
+}
+
+/// Returns `PartialEq` and `PartialOrd` impls in both directions.
+///
+/// Each operand is given as the pair of its type and the expression to get a reference to the
+/// base type from `outer` or `target`, which is a reference to the operand.
+pub fn gen_cmp_pair(
+    (ty_outer, outer_to_base): (&TokenStream, &TokenStream),
+    (ty_target, target_to_base): (&TokenStream, &TokenStream),
+    ty_base: &TokenStream,
+) -> Vec<CmpImpl> {
+    let partial_eq = quote!(std::cmp::PartialEq);
+    let partial_ord = quote!(std::cmp::PartialOrd);
+    let eq = quote!(<#ty_base as #partial_eq>::eq);
+    let partial_cmp = quote!(<#ty_base as #partial_ord>::partial_cmp);
+    let ordering = quote!(std::option::Option<std::cmp::Ordering>);
+    vec![
+        CmpImpl {
+            trait_name: "PartialEq",
+            trait_: partial_eq.clone(),
+            ty_trait: quote!(#partial_eq<#ty_target>),
+            ty_self: ty_outer.clone(),
+            body: quote! {
+                fn eq(&self, other: &#ty_target) -> bool {
+                    let (outer, target) = (self, other);
+                    #eq(#outer_to_base, #target_to_base)
+                }
+            },
+        },
+        CmpImpl {
+            trait_name: "PartialEq",
+            trait_: partial_eq.clone(),
+            ty_trait: quote!(#partial_eq<#ty_outer>),
+            ty_self: ty_target.clone(),
+            body: quote! {
+                fn eq(&self, other: &#ty_outer) -> bool {
+                    let (outer, target) = (other, self);
+                    #eq(#target_to_base, #outer_to_base)
+                }
+            },
+        },
+        CmpImpl {
+            trait_name: "PartialOrd",
+            trait_: partial_ord.clone(),
+            ty_trait: quote!(#partial_ord<#ty_target>),
+            ty_self: ty_outer.clone(),
+            body: quote! {
+                fn partial_cmp(&self, other: &#ty_target) -> #ordering {
+                    let (outer, target) = (self, other);
+                    #partial_cmp(#outer_to_base, #target_to_base)
+                }
+            },
+        },
+        CmpImpl {
+            trait_name: "PartialOrd",
+            trait_: partial_ord.clone(),
+            ty_trait: quote!(#partial_ord<#ty_outer>),
+            ty_self: ty_target.clone(),
+            body: quote! {
+                fn partial_cmp(&self, other: &#ty_outer) -> #ordering {
+                    let (outer, target) = (other, self);
+                    #partial_cmp(#target_to_base, #outer_to_base)
+                }
+            },
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmp_with_inner() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(cmp_with(inner))]
+            pub struct MyInt(i32);
+        };
+        let toks = gen_cmp_with(&Input::new(&input).unwrap(), Kind::Sized);
+        let as_inner = quote!(<MyInt as opaque_typedef::OpaqueTypedefSized>::as_inner);
+        let eq = quote!(<i32 as std::cmp::PartialEq>::eq);
+        let partial_cmp = quote!(<i32 as std::cmp::PartialOrd>::partial_cmp);
+        let ordering = quote!(std::option::Option<std::cmp::Ordering>);
+        let expected = quote! {
+            impl std::cmp::PartialEq<i32> for MyInt {
+                fn eq(&self, other: &i32) -> bool {
+                    let (outer, target) = (self, other);
+                    #eq(#as_inner(outer), target)
+                }
+            }
+            impl std::cmp::PartialEq<MyInt> for i32 {
+                fn eq(&self, other: &MyInt) -> bool {
+                    let (outer, target) = (other, self);
+                    #eq(target, #as_inner(outer))
+                }
+            }
+            impl std::cmp::PartialOrd<i32> for MyInt {
+                fn partial_cmp(&self, other: &i32) -> #ordering {
+                    let (outer, target) = (self, other);
+                    #partial_cmp(#as_inner(outer), target)
+                }
+            }
+            impl std::cmp::PartialOrd<MyInt> for i32 {
+                fn partial_cmp(&self, other: &MyInt) -> #ordering {
+                    let (outer, target) = (other, self);
+                    #partial_cmp(target, #as_inner(outer))
+                }
+            }
+            impl std::cmp::PartialEq<i32> for &'_ MyInt {
+                fn eq(&self, other: &i32) -> bool {
+                    let (outer, target) = (self, other);
+                    #eq(#as_inner(*outer), target)
+                }
+            }
+            impl std::cmp::PartialEq<&'_ MyInt> for i32 {
+                fn eq(&self, other: & &'_ MyInt) -> bool {
+                    let (outer, target) = (other, self);
+                    #eq(target, #as_inner(*outer))
+                }
+            }
+            impl std::cmp::PartialOrd<i32> for &'_ MyInt {
+                fn partial_cmp(&self, other: &i32) -> #ordering {
+                    let (outer, target) = (self, other);
+                    #partial_cmp(#as_inner(*outer), target)
+                }
+            }
+            impl std::cmp::PartialOrd<&'_ MyInt> for i32 {
+                fn partial_cmp(&self, other: & &'_ MyInt) -> #ordering {
+                    let (outer, target) = (other, self);
+                    #partial_cmp(target, #as_inner(*outer))
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    /// Impls for `&Outer` should not be generated for reference targets.
+    #[test]
+    fn cmp_with_ref_target() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(cmp_with(base = str, "&str"))]
+            pub struct MyString(String);
+        };
+        let toks = gen_cmp_with(&Input::new(&input).unwrap(), Kind::Sized);
+        let as_inner = quote!(<MyString as opaque_typedef::OpaqueTypedefSized>::as_inner);
+        let outer_to_base = quote!(<String as std::convert::AsRef<str>>::as_ref(#as_inner(outer)));
+        let target_to_base = quote!(<&str as std::convert::AsRef<str>>::as_ref(target));
+        let expected = quote! {
+            impl std::cmp::PartialEq<&str> for MyString {
+                fn eq(&self, other: & &str) -> bool {
+                    let (outer, target) = (self, other);
+                    <str as std::cmp::PartialEq>::eq(#outer_to_base, #target_to_base)
+                }
+            }
+            impl std::cmp::PartialEq<MyString> for &str {
+                fn eq(&self, other: &MyString) -> bool {
+                    let (outer, target) = (other, self);
+                    <str as std::cmp::PartialEq>::eq(#target_to_base, #outer_to_base)
+                }
+            }
+            impl std::cmp::PartialOrd<&str> for MyString {
+                fn partial_cmp(&self, other: & &str) -> std::option::Option<std::cmp::Ordering> {
+                    let (outer, target) = (self, other);
+                    <str as std::cmp::PartialOrd>::partial_cmp(#outer_to_base, #target_to_base)
+                }
+            }
+            impl std::cmp::PartialOrd<MyString> for &str {
+                fn partial_cmp(&self, other: &MyString) -> std::option::Option<std::cmp::Ordering> {
+                    let (outer, target) = (other, self);
+                    <str as std::cmp::PartialOrd>::partial_cmp(#target_to_base, #outer_to_base)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+}
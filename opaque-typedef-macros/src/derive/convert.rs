@@ -0,0 +1,250 @@
+//! `From` and `TryFrom` codegen for conversions between the type and the inner type.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{derive::Kind, input::Input};
+
+/// Generate impls for `From` and `TryFrom`.
+///
+/// The conversion from the inner type is `From` for types without validator, and `TryFrom` for
+/// types with validator.
+/// `From` uses `try_from_inner` with the infallible error, so the `*Infallible` traits are not
+/// required.
+/// For unsized types, the conversions are implemented for references.
+pub fn gen_convert(input: &Input, kind: Kind) -> TokenStream {
+    let convert = input.convert();
+    let mut toks = TokenStream::new();
+    if convert.impl_from_inner() {
+        toks.extend(match kind {
+            Kind::Sized => gen_from_inner_sized(input),
+            Kind::Unsized => gen_from_inner_unsized(input),
+        });
+    }
+    if convert.impl_into_inner() {
+        toks.extend(match kind {
+            Kind::Sized => gen_into_inner_sized(input),
+            Kind::Unsized => gen_into_inner_unsized(input),
+        });
+    }
+    toks
+}
+
+/// Returns the generics for the impl of the trait split for impl, with the lifetime `'__a`
+/// inserted at the front.
+fn generics_with_lifetime(input: &Input, trait_name: &str) -> (TokenStream, TokenStream) {
+    let mut generics = input.generics_for(trait_name, None);
+    generics.params.insert(0, syn::parse_quote!('__a));
+    let (generics_impl, _, generics_where) = generics.split_for_impl();
+    (quote!(#generics_impl), quote!(#generics_where))
+}
+
+/// Generate `From<Inner>` or `TryFrom<Inner>` for the sized type.
+fn gen_from_inner_sized(input: &Input) -> TokenStream {
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = Kind::Sized.base_trait();
+    let trait_name = match input.validator() {
+        None => "From",
+        Some(_) => "TryFrom",
+    };
+    let generics = input.generics_for(trait_name, None);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+
+    if input.validator().is_none() {
+        quote! {
+            impl #generics_impl std::convert::From<#ty_inner> for #ty #generics_ty #generics_where {
+                fn from(inner: #ty_inner) -> Self {
+                    <Self as #base_trait>::try_from_inner(inner)
+                        .unwrap_or_else(|e: std::convert::Infallible| match e {})
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #generics_impl std::convert::TryFrom<#ty_inner> for #ty #generics_ty
+                #generics_where
+            {
+                type Error = <Self as #base_trait>::Error;
+
+                fn try_from(inner: #ty_inner) -> std::result::Result<Self, Self::Error> {
+                    <Self as #base_trait>::try_from_inner(inner)
+                }
+            }
+        }
+    }
+}
+
+/// Generate `From<&Inner>` or `TryFrom<&Inner>` for the reference to the unsized type.
+fn gen_from_inner_unsized(input: &Input) -> TokenStream {
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = Kind::Unsized.base_trait();
+    let (_, generics_ty, _) = input.generics().split_for_impl();
+    let trait_name = match input.validator() {
+        None => "From",
+        Some(_) => "TryFrom",
+    };
+    let (generics_impl, generics_where) = generics_with_lifetime(input, trait_name);
+    let ty_outer = quote!(#ty #generics_ty);
+
+    if input.validator().is_none() {
+        quote! {
+            impl #generics_impl std::convert::From<&'__a #ty_inner> for &'__a #ty_outer
+                #generics_where
+            {
+                fn from(inner: &'__a #ty_inner) -> Self {
+                    <#ty_outer as #base_trait>::try_from_inner(inner)
+                        .unwrap_or_else(|e: std::convert::Infallible| match e {})
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #generics_impl std::convert::TryFrom<&'__a #ty_inner> for &'__a #ty_outer
+                #generics_where
+            {
+                type Error = <#ty_outer as opaque_typedef::TransparentUnsized>::Error;
+
+                fn try_from(inner: &'__a #ty_inner) -> std::result::Result<Self, Self::Error> {
+                    <#ty_outer as #base_trait>::try_from_inner(inner)
+                }
+            }
+        }
+    }
+}
+
+/// Generate `From<Outer>` for the inner type.
+fn gen_into_inner_sized(input: &Input) -> TokenStream {
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = Kind::Sized.base_trait();
+    let generics = input.generics_for("From", None);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+    let ty_outer = quote!(#ty #generics_ty);
+
+    quote! {
+        impl #generics_impl std::convert::From<#ty_outer> for #ty_inner #generics_where {
+            fn from(outer: #ty_outer) -> Self {
+                <#ty_outer as #base_trait>::into_inner(outer)
+            }
+        }
+    }
+}
+
+/// Generate `From<&Outer>` for the reference to the inner type.
+fn gen_into_inner_unsized(input: &Input) -> TokenStream {
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = Kind::Unsized.base_trait();
+    let (_, generics_ty, _) = input.generics().split_for_impl();
+    let (generics_impl, generics_where) = generics_with_lifetime(input, "From");
+    let ty_outer = quote!(#ty #generics_ty);
+
+    quote! {
+        impl #generics_impl std::convert::From<&'__a #ty_outer> for &'__a #ty_inner
+            #generics_where
+        {
+            fn from(outer: &'__a #ty_outer) -> Self {
+                <#ty_outer as #base_trait>::as_inner(outer)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sized_infallible() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(convert(from_inner, into_inner))]
+            pub struct MyInt(i32);
+        };
+        let toks = gen_convert(&Input::new(&input).unwrap(), Kind::Sized);
+        let expected = quote! {
+            impl std::convert::From<i32> for MyInt {
+                fn from(inner: i32) -> Self {
+                    <Self as opaque_typedef::OpaqueTypedefSized>::try_from_inner(inner)
+                        .unwrap_or_else(|e: std::convert::Infallible| match e {})
+                }
+            }
+            impl std::convert::From<MyInt> for i32 {
+                fn from(outer: MyInt) -> Self {
+                    <MyInt as opaque_typedef::OpaqueTypedefSized>::into_inner(outer)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn sized_validated() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(validate(error = "OddError", validator = "validate_even"))]
+            #[opaque_typedef(convert(from_inner))]
+            pub struct EvenInt(i32);
+        };
+        let toks = gen_convert(&Input::new(&input).unwrap(), Kind::Sized);
+        let expected = quote! {
+            impl std::convert::TryFrom<i32> for EvenInt {
+                type Error = <Self as opaque_typedef::OpaqueTypedefSized>::Error;
+
+                fn try_from(inner: i32) -> std::result::Result<Self, Self::Error> {
+                    <Self as opaque_typedef::OpaqueTypedefSized>::try_from_inner(inner)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn unsized_infallible() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(convert(from_inner))]
+            pub struct MyStr(str);
+        };
+        let toks = gen_convert(&Input::new(&input).unwrap(), Kind::Unsized);
+        let expected = quote! {
+            impl<'__a> std::convert::From<&'__a str> for &'__a MyStr {
+                fn from(inner: &'__a str) -> Self {
+                    <MyStr as opaque_typedef::OpaqueTypedefUnsized>::try_from_inner(inner)
+                        .unwrap_or_else(|e: std::convert::Infallible| match e {})
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn unsized_validated() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(validate(error = "AsciiError", validator = "validate_str"))]
+            #[opaque_typedef(convert(from_inner, into_inner))]
+            pub struct AsciiStr(str);
+        };
+        let toks = gen_convert(&Input::new(&input).unwrap(), Kind::Unsized);
+        let expected = quote! {
+            impl<'__a> std::convert::TryFrom<&'__a str> for &'__a AsciiStr {
+                type Error = <AsciiStr as opaque_typedef::TransparentUnsized>::Error;
+
+                fn try_from(inner: &'__a str) -> std::result::Result<Self, Self::Error> {
+                    <AsciiStr as opaque_typedef::OpaqueTypedefUnsized>::try_from_inner(inner)
+                }
+            }
+            impl<'__a> std::convert::From<&'__a AsciiStr> for &'__a str {
+                fn from(outer: &'__a AsciiStr) -> Self {
+                    <AsciiStr as opaque_typedef::OpaqueTypedefUnsized>::as_inner(outer)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+}
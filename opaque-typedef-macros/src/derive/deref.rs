@@ -0,0 +1,142 @@
+//! `Deref` and `DerefMut` codegen.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{derive::Kind, input::Input};
+
+/// Generate impls for `Deref` and `DerefMut`.
+///
+/// `DerefMut` uses the `*Mut` trait of the kind, so it should also be derived.
+pub fn gen_deref(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    let deref = match input.deref() {
+        Some(v) => v,
+        None => return Ok(TokenStream::new()),
+    };
+    if let Some(span) = deref.mutable() {
+        if input.validator().is_some() {
+            // `DerefMut` gives implicit mutable access, so it is allowed only when the validation
+            // never fails.
+            return Err(syn::Error::new(
+                span,
+                "`deref(mut)` is available only for types without validator",
+            ));
+        }
+    }
+
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = kind.base_trait();
+    let mut_trait = kind.mut_trait();
+    let expr_inner = quote!(<Self as #base_trait>::as_inner(self));
+    let expr_inner_mut = quote!(<Self as #mut_trait>::as_inner_mut(self));
+
+    let (ty_target, expr, expr_mut, predicate, predicate_mut) = match deref.target() {
+        None => (quote!(#ty_inner), expr_inner, expr_inner_mut, None, None),
+        Some(ty_target) => (
+            quote!(#ty_target),
+            quote!(<#ty_inner as std::ops::Deref>::deref(#expr_inner)),
+            quote!(<#ty_inner as std::ops::DerefMut>::deref_mut(#expr_inner_mut)),
+            Some(syn::parse_quote!(#ty_inner: std::ops::Deref<Target = #ty_target>)),
+            Some(syn::parse_quote!(#ty_inner: std::ops::DerefMut<Target = #ty_target>)),
+        ),
+    };
+
+    let generics = input.generics_for("Deref", predicate);
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+    let mut toks = quote! {
+        impl #generics_impl std::ops::Deref for #ty #generics_ty #generics_where {
+            type Target = #ty_target;
+
+            fn deref(&self) -> &Self::Target {
+                #expr
+            }
+        }
+    };
+    if deref.mutable().is_some() {
+        let generics = input.generics_for("DerefMut", predicate_mut);
+        let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+        toks.extend(quote! {
+            impl #generics_impl std::ops::DerefMut for #ty #generics_ty #generics_where {
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    #expr_mut
+                }
+            }
+        });
+    }
+
+    Ok(toks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deref_inner() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(deref(target = "inner"))]
+            pub struct MyStr(str);
+        };
+        let toks = gen_deref(&Input::new(&input).unwrap(), Kind::Unsized).unwrap();
+        let expected = quote! {
+            impl std::ops::Deref for MyStr {
+                type Target = str;
+
+                fn deref(&self) -> &Self::Target {
+                    <Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner(self)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn deref_mut_target() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+            #[opaque_typedef(deref(target = str, mut))]
+            pub struct MyString(String);
+        };
+        let toks = gen_deref(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            impl std::ops::Deref for MyString
+            where
+                String: std::ops::Deref<Target = str>
+            {
+                type Target = str;
+
+                fn deref(&self) -> &Self::Target {
+                    <String as std::ops::Deref>::deref(
+                        <Self as opaque_typedef::OpaqueTypedefSized>::as_inner(self)
+                    )
+                }
+            }
+            impl std::ops::DerefMut for MyString
+            where
+                String: std::ops::DerefMut<Target = str>
+            {
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    <String as std::ops::DerefMut>::deref_mut(
+                        <Self as opaque_typedef::OpaqueTypedefSizedMut>::as_inner_mut(self)
+                    )
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn deref_mut_with_validator() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+            #[opaque_typedef(deref(target = str, mut), allow_unchecked_mut)]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            pub struct MyString(String);
+        };
+        let toks = gen_deref(&Input::new(&input).unwrap(), Kind::Sized);
+        assert!(toks.is_err());
+    }
+}
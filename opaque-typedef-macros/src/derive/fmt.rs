@@ -0,0 +1,149 @@
+//! Formatting traits codegen.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Ident;
+
+use crate::{attr::DebugMode, derive::Kind, input::Input};
+
+/// Generate impls for formatting traits.
+///
+/// The formatter is passed to the inner value as is, so flags such as width and precision are
+/// respected.
+pub fn gen_fmt(input: &Input, kind: Kind) -> TokenStream {
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = kind.base_trait();
+    let expr_inner = quote!(<Self as #base_trait>::as_inner(self));
+
+    let gen_impl = |trait_: &Ident, body: TokenStream| {
+        let generics = input.generics_for(
+            &trait_.to_string(),
+            Some(syn::parse_quote!(#ty_inner: std::fmt::#trait_)),
+        );
+        let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+        quote! {
+            impl #generics_impl std::fmt::#trait_ for #ty #generics_ty #generics_where {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    #body
+                }
+            }
+        }
+    };
+
+    let mut toks = input
+        .fmt()
+        .traits()
+        .iter()
+        .map(|name| {
+            let trait_ = Ident::new(name, Span::call_site());
+            gen_impl(
+                &trait_,
+                quote!(<#ty_inner as std::fmt::#trait_>::fmt(#expr_inner, f)),
+            )
+        })
+        .collect::<TokenStream>();
+
+    if let Some(mode) = input.fmt().debug() {
+        let trait_ = Ident::new("Debug", Span::call_site());
+        let body = match mode {
+            DebugMode::Transparent => quote!(<#ty_inner as std::fmt::Debug>::fmt(#expr_inner, f)),
+            DebugMode::Named => {
+                let name = ty.to_string();
+                quote!(f.debug_tuple(#name).field(&#expr_inner).finish())
+            }
+        };
+        toks.extend(gen_impl(&trait_, body));
+    }
+
+    toks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_hex() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(fmt(display, lower_hex))]
+            pub struct MyInt<T>(T);
+        };
+        let toks = gen_fmt(&Input::new(&input).unwrap(), Kind::Sized);
+        let expected = quote! {
+            impl<T> std::fmt::Display for MyInt<T>
+            where
+                T: std::fmt::Display
+            {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    <T as std::fmt::Display>::fmt(
+                        <Self as opaque_typedef::OpaqueTypedefSized>::as_inner(self),
+                        f
+                    )
+                }
+            }
+            impl<T> std::fmt::LowerHex for MyInt<T>
+            where
+                T: std::fmt::LowerHex
+            {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    <T as std::fmt::LowerHex>::fmt(
+                        <Self as opaque_typedef::OpaqueTypedefSized>::as_inner(self),
+                        f
+                    )
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn debug_transparent() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(fmt(debug = "transparent"))]
+            pub struct MyStr(str);
+        };
+        let toks = gen_fmt(&Input::new(&input).unwrap(), Kind::Unsized);
+        let expected = quote! {
+            impl std::fmt::Debug for MyStr
+            where
+                str: std::fmt::Debug
+            {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    <str as std::fmt::Debug>::fmt(
+                        <Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner(self),
+                        f
+                    )
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn debug_named() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(fmt(debug = named))]
+            pub struct MyStr(str);
+        };
+        let toks = gen_fmt(&Input::new(&input).unwrap(), Kind::Unsized);
+        let expected = quote! {
+            impl std::fmt::Debug for MyStr
+            where
+                str: std::fmt::Debug
+            {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.debug_tuple("MyStr")
+                        .field(&<Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner(self))
+                        .finish()
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+}
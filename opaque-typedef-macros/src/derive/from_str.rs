@@ -0,0 +1,93 @@
+//! `FromStr` codegen.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{derive::Kind, input::Input};
+
+/// Generate impl for `FromStr`.
+///
+/// The string is parsed as the inner type, and then validated by `try_from_inner`.
+pub fn gen_from_str(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    let span = match input.impl_from_str() {
+        Some(v) => v,
+        None => return Ok(TokenStream::new()),
+    };
+    if kind != Kind::Sized {
+        return Err(syn::Error::new(
+            span,
+            "`from_str` is only available for `OpaqueTypedefSized` types",
+        ));
+    }
+
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = kind.base_trait();
+    let generics = input.generics_for(
+        "FromStr",
+        Some(syn::parse_quote!(#ty_inner: std::str::FromStr)),
+    );
+    let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #generics_impl std::str::FromStr for #ty #generics_ty #generics_where {
+            type Err = opaque_typedef::FromStrError<
+                <#ty_inner as std::str::FromStr>::Err,
+                <Self as #base_trait>::Error,
+            >;
+
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                let inner = <#ty_inner as std::str::FromStr>::from_str(s)
+                    .map_err(opaque_typedef::FromStrError::Parse)?;
+                <Self as #base_trait>::try_from_inner(inner)
+                    .map_err(opaque_typedef::FromStrError::Validation)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(from_str)]
+            #[opaque_typedef(validate(error = PortError, validator = validate_port))]
+            pub struct Port(u16);
+        };
+        let toks = gen_from_str(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            impl std::str::FromStr for Port
+            where
+                u16: std::str::FromStr
+            {
+                type Err = opaque_typedef::FromStrError<
+                    <u16 as std::str::FromStr>::Err,
+                    <Self as opaque_typedef::OpaqueTypedefSized>::Error,
+                >;
+
+                fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                    let inner = <u16 as std::str::FromStr>::from_str(s)
+                        .map_err(opaque_typedef::FromStrError::Parse)?;
+                    <Self as opaque_typedef::OpaqueTypedefSized>::try_from_inner(inner)
+                        .map_err(opaque_typedef::FromStrError::Validation)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn unsized_type() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(from_str)]
+            pub struct MyStr(str);
+        };
+        assert!(gen_from_str(&Input::new(&input).unwrap(), Kind::Unsized).is_err());
+    }
+}
@@ -0,0 +1,679 @@
+//! Operator traits codegen.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Generics, Ident, Lifetime, Type, WherePredicate};
+
+use crate::{
+    attr::{OnInvalid, OpAttr, OpKind, OpTrait},
+    derive::Kind,
+    input::Input,
+};
+
+/// Generate impls for operator traits.
+///
+/// Operators are applied to the inner values, and the results are converted into the outer type.
+/// Results of validated types are validated by `try_from_inner`, and it panics or returns
+/// `Result` on invalid results, depending on `on_invalid`.
+pub fn gen_ops(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    let ops = input.ops();
+    let span = match ops.span() {
+        Some(v) => v,
+        None => return Ok(TokenStream::new()),
+    };
+    if kind != Kind::Sized {
+        return Err(syn::Error::new(
+            span,
+            "`ops` is only available for `OpaqueTypedefSized` types",
+        ));
+    }
+    let on_invalid = match ops.on_invalid() {
+        Some((span, OnInvalid::Result)) => {
+            if ops
+                .traits()
+                .iter()
+                .any(|op| op.op().kind() == OpKind::Assign)
+            {
+                // Compound assignment cannot return the error.
+                return Err(syn::Error::new(
+                    span,
+                    "Compound assignment operators cannot be used with `on_invalid = \"result\"`",
+                ));
+            }
+            OnInvalid::Result
+        }
+        _ => OnInvalid::Panic,
+    };
+
+    let gen = OpsGen::new(input, on_invalid);
+    let mut toks = ops
+        .traits()
+        .iter()
+        .map(|op| match op.op().kind() {
+            OpKind::Unary => gen.gen_unary(op.op()),
+            OpKind::Binary => gen.gen_binary(op),
+            OpKind::Assign => gen.gen_assign(op),
+        })
+        .collect::<TokenStream>();
+    if ops.mask() {
+        toks.extend(gen.gen_mask());
+    }
+    Ok(toks)
+}
+
+/// Operand of operators.
+#[derive(Clone, Copy)]
+enum Operand<'a> {
+    /// Outer type.
+    Outer,
+    /// Reference to the outer type.
+    OuterRef,
+    /// Inner type.
+    Inner,
+    /// Other type given by `rhs = ..`.
+    Other(&'a Type),
+}
+
+/// Returns the right operands of the binary operator or the compound assignment.
+///
+/// Shift operators take only the specified type, and the others take the outer type, the
+/// reference to it, and the inner type.
+fn rhs_operands(op: &OpAttr) -> Vec<Operand<'_>> {
+    match op.rhs() {
+        Some(ty) => vec![Operand::Other(ty)],
+        None => vec![Operand::Outer, Operand::OuterRef, Operand::Inner],
+    }
+}
+
+/// Codegen context for operator traits.
+struct OpsGen<'a> {
+    /// Input.
+    input: &'a Input<'a>,
+    /// Behavior on invalid results.
+    on_invalid: OnInvalid,
+    /// Outer type.
+    ty_outer: TokenStream,
+    /// Inner type.
+    ty_inner: TokenStream,
+    /// Base trait.
+    base_trait: TokenStream,
+}
+
+impl<'a> OpsGen<'a> {
+    /// Creates a new context.
+    fn new(input: &'a Input<'a>, on_invalid: OnInvalid) -> Self {
+        let ty = input.ident();
+        let (_, generics_ty, _) = input.generics().split_for_impl();
+        let ty_inner = input.primary_field().ty();
+        Self {
+            input,
+            on_invalid,
+            ty_outer: quote!(#ty #generics_ty),
+            ty_inner: quote!(#ty_inner),
+            base_trait: Kind::Sized.base_trait(),
+        }
+    }
+
+    /// Returns the type of the operand, and the type of the inner value of the operand.
+    ///
+    /// `lifetime` is used for references.
+    fn operand_types(&self, operand: Operand, lifetime: &Lifetime) -> (TokenStream, TokenStream) {
+        let Self {
+            ty_outer, ty_inner, ..
+        } = self;
+        match operand {
+            Operand::Outer => (ty_outer.clone(), ty_inner.clone()),
+            Operand::OuterRef => (quote!(&#lifetime #ty_outer), quote!(&#lifetime #ty_inner)),
+            Operand::Inner => (ty_inner.clone(), ty_inner.clone()),
+            Operand::Other(ty) => (quote!(#ty), quote!(#ty)),
+        }
+    }
+
+    /// Returns the expression to get the inner value of the operand.
+    fn operand_inner(&self, operand: Operand, expr: TokenStream) -> TokenStream {
+        let Self {
+            ty_outer,
+            base_trait,
+            ..
+        } = self;
+        match operand {
+            Operand::Outer => quote!(<#ty_outer as #base_trait>::into_inner(#expr)),
+            Operand::OuterRef => quote!(<#ty_outer as #base_trait>::as_inner(#expr)),
+            Operand::Inner | Operand::Other(_) => expr,
+        }
+    }
+
+    /// Returns the output type and the expression to convert the inner result into it.
+    fn output(&self, op: &OpTrait, expr: TokenStream) -> (TokenStream, TokenStream) {
+        let Self {
+            ty_outer,
+            base_trait,
+            ..
+        } = self;
+        match self.on_invalid {
+            OnInvalid::Result => (
+                quote!(std::result::Result<#ty_outer, <#ty_outer as #base_trait>::Error>),
+                quote!(<#ty_outer as #base_trait>::try_from_inner(#expr)),
+            ),
+            OnInvalid::Panic => (ty_outer.clone(), self.wrap_or_panic(op.name(), expr)),
+        }
+    }
+
+    /// Returns the expression to convert the inner value into the outer type.
+    ///
+    /// For validated types, it panics with the name of the operation if the value is invalid.
+    fn wrap_or_panic(&self, name: &str, expr: TokenStream) -> TokenStream {
+        let Self {
+            ty_outer,
+            base_trait,
+            ..
+        } = self;
+        if self.input.validator().is_none() {
+            quote!(<#ty_outer as opaque_typedef::OpaqueTypedefSizedInfallible>::from_inner(#expr))
+        } else {
+            let msg = format!("Invalid result of `{}`", name);
+            quote!(<#ty_outer as #base_trait>::try_from_inner(#expr).expect(#msg))
+        }
+    }
+
+    /// Returns the generics for the impl of the trait with the given lifetimes and predicates.
+    ///
+    /// The generics are for the inherent impl if the trait is not given.
+    fn generics(
+        &self,
+        trait_: Option<&Ident>,
+        lifetimes: &[&Lifetime],
+        predicates: Vec<WherePredicate>,
+    ) -> Generics {
+        let mut generics = match trait_ {
+            Some(trait_) => self.input.generics_for(&trait_.to_string(), predicates),
+            None => self.input.generics_for_inherent(predicates),
+        };
+        for (i, lifetime) in lifetimes.iter().enumerate() {
+            generics.params.insert(i, syn::parse_quote!(#lifetime));
+        }
+        generics
+    }
+
+    /// Generates impls for unary operators.
+    fn gen_unary(&self, op: &OpTrait) -> TokenStream {
+        let trait_ = Ident::new(op.name(), Span::call_site());
+        let method = Ident::new(op.method(), Span::call_site());
+        let ty_inner = &self.ty_inner;
+        let lt_lhs = Lifetime::new("'__lhs", Span::call_site());
+
+        [Operand::Outer, Operand::OuterRef]
+            .iter()
+            .map(|&lhs| {
+                let (ty_lhs, ty_lhs_inner) = self.operand_types(lhs, &lt_lhs);
+                let expr_lhs = self.operand_inner(lhs, quote!(self));
+                let (ty_output, expr_output) = self.output(
+                    op,
+                    quote!(<#ty_lhs_inner as std::ops::#trait_>::#method(#expr_lhs)),
+                );
+                let lifetimes = match lhs {
+                    Operand::OuterRef => vec![&lt_lhs],
+                    _ => vec![],
+                };
+                let predicate =
+                    syn::parse_quote!(#ty_lhs_inner: std::ops::#trait_<Output = #ty_inner>);
+                let generics = self.generics(Some(&trait_), &lifetimes, vec![predicate]);
+                let (generics_impl, _, generics_where) = generics.split_for_impl();
+                quote! {
+                    impl #generics_impl std::ops::#trait_ for #ty_lhs #generics_where {
+                        type Output = #ty_output;
+
+                        fn #method(self) -> Self::Output {
+                            #expr_output
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Generates impls for binary operators.
+    fn gen_binary(&self, op_attr: &OpAttr) -> TokenStream {
+        let op = op_attr.op();
+        let trait_ = Ident::new(op.name(), Span::call_site());
+        let method = Ident::new(op.method(), Span::call_site());
+        let ty_inner = &self.ty_inner;
+        let lt_lhs = Lifetime::new("'__lhs", Span::call_site());
+        let lt_rhs = Lifetime::new("'__rhs", Span::call_site());
+
+        let mut toks = TokenStream::new();
+        for &lhs in &[Operand::Outer, Operand::OuterRef] {
+            for &rhs in &rhs_operands(op_attr) {
+                let (ty_lhs, ty_lhs_inner) = self.operand_types(lhs, &lt_lhs);
+                let (ty_rhs, ty_rhs_inner) = self.operand_types(rhs, &lt_rhs);
+                let expr_lhs = self.operand_inner(lhs, quote!(self));
+                let expr_rhs = self.operand_inner(rhs, quote!(rhs));
+                let (ty_output, expr_output) = self.output(
+                    op,
+                    quote! {
+                        <#ty_lhs_inner as std::ops::#trait_<#ty_rhs_inner>>::#method(
+                            #expr_lhs,
+                            #expr_rhs
+                        )
+                    },
+                );
+                let mut lifetimes = Vec::new();
+                if let Operand::OuterRef = lhs {
+                    lifetimes.push(&lt_lhs);
+                }
+                if let Operand::OuterRef = rhs {
+                    lifetimes.push(&lt_rhs);
+                }
+                let predicate = syn::parse_quote! {
+                    #ty_lhs_inner: std::ops::#trait_<#ty_rhs_inner, Output = #ty_inner>
+                };
+                let generics = self.generics(Some(&trait_), &lifetimes, vec![predicate]);
+                let (generics_impl, _, generics_where) = generics.split_for_impl();
+                toks.extend(quote! {
+                    impl #generics_impl std::ops::#trait_<#ty_rhs> for #ty_lhs #generics_where {
+                        type Output = #ty_output;
+
+                        fn #method(self, rhs: #ty_rhs) -> Self::Output {
+                            #expr_output
+                        }
+                    }
+                });
+            }
+        }
+        toks
+    }
+
+    /// Generates impls for compound assignment operators.
+    ///
+    /// For validated types, the inner value is modified through `try_modify_inner`, so it is
+    /// validated after the operation.
+    /// Otherwise, the inner field is modified in place, because any result is valid.
+    fn gen_assign(&self, op_attr: &OpAttr) -> TokenStream {
+        let op = op_attr.op();
+        let trait_ = Ident::new(op.name(), Span::call_site());
+        let method = Ident::new(op.method(), Span::call_site());
+        let Self {
+            ty_outer,
+            ty_inner,
+            base_trait,
+            ..
+        } = self;
+        let lt_rhs = Lifetime::new("'__rhs", Span::call_site());
+        let msg = format!("Invalid result of `{}`", op.name());
+        let field = self.input.primary_field().accessor();
+
+        rhs_operands(op_attr)
+            .into_iter()
+            .map(|rhs| {
+                let (ty_rhs, ty_rhs_inner) = self.operand_types(rhs, &lt_rhs);
+                let expr_rhs = self.operand_inner(rhs, quote!(rhs));
+                let lifetimes = match rhs {
+                    Operand::OuterRef => vec![&lt_rhs],
+                    _ => vec![],
+                };
+                let mut predicates =
+                    vec![syn::parse_quote!(#ty_inner: std::ops::#trait_<#ty_rhs_inner>)];
+                let body = if self.input.validator().is_none() {
+                    quote! {
+                        <#ty_inner as std::ops::#trait_<#ty_rhs_inner>>::#method(
+                            &mut self.#field,
+                            #expr_rhs
+                        )
+                    }
+                } else {
+                    predicates.push(syn::parse_quote!(#ty_inner: std::clone::Clone));
+                    quote! {
+                        let rhs = #expr_rhs;
+                        <#ty_outer as #base_trait>::try_modify_inner(self, |inner| {
+                            <#ty_inner as std::ops::#trait_<#ty_rhs_inner>>::#method(inner, rhs)
+                        })
+                        .expect(#msg)
+                    }
+                };
+                let generics = self.generics(Some(&trait_), &lifetimes, predicates);
+                let (generics_impl, _, generics_where) = generics.split_for_impl();
+                quote! {
+                    impl #generics_impl std::ops::#trait_<#ty_rhs> for #ty_outer #generics_where {
+                        fn #method(&mut self, rhs: #ty_rhs) {
+                            #body
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Generates helper methods for bit masks.
+    ///
+    /// Results of `insert` and `remove` are validated in the same way as operators.
+    /// With `on_invalid = "result"`, they return `Result<(), Self::Error>` and leave the value
+    /// unmodified on invalid results.
+    fn gen_mask(&self) -> TokenStream {
+        let Self {
+            ty_outer,
+            ty_inner,
+            base_trait,
+            ..
+        } = self;
+        let ty = self.input.ident();
+        let predicates = vec![
+            syn::parse_quote! {
+                for<'__a> &'__a #ty_inner: std::ops::BitAnd<&'__a #ty_inner, Output = #ty_inner>
+                    + std::ops::BitOr<&'__a #ty_inner, Output = #ty_inner>
+                    + std::ops::Not<Output = #ty_inner>
+            },
+            syn::parse_quote!(#ty_inner: std::cmp::PartialEq),
+        ];
+        let generics = self.generics(None, &[], predicates);
+        let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+        let as_inner = quote!(<#ty_outer as #base_trait>::as_inner);
+        let (ret, stmt_insert, stmt_remove) = match self.on_invalid {
+            OnInvalid::Result => (
+                quote!(-> std::result::Result<(), <#ty_outer as #base_trait>::Error>),
+                quote! {
+                    *self = <#ty_outer as #base_trait>::try_from_inner(inserted)?;
+                    Ok(())
+                },
+                quote! {
+                    *self = <#ty_outer as #base_trait>::try_from_inner(removed)?;
+                    Ok(())
+                },
+            ),
+            OnInvalid::Panic => {
+                let expr_insert = self.wrap_or_panic("insert", quote!(inserted));
+                let expr_remove = self.wrap_or_panic("remove", quote!(removed));
+                (
+                    quote!(),
+                    quote!(*self = #expr_insert;),
+                    quote!(*self = #expr_remove;),
+                )
+            }
+        };
+
+        quote! {
+            impl #generics_impl #ty #generics_ty #generics_where {
+                /// Returns `true` if all the bits in `other` are set in `self`.
+                pub fn contains(&self, other: &Self) -> bool {
+                    let other = #as_inner(other);
+                    (#as_inner(self) & other) == *other
+                }
+
+                /// Sets the bits in `other` to `self`.
+                pub fn insert(&mut self, other: &Self) #ret {
+                    let inserted = #as_inner(self) | #as_inner(other);
+                    #stmt_insert
+                }
+
+                /// Clears the bits in `other` from `self`.
+                pub fn remove(&mut self, other: &Self) #ret {
+                    let removed = #as_inner(self) & &!#as_inner(other);
+                    #stmt_remove
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neg_infallible() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+            #[opaque_typedef(ops(neg))]
+            pub struct MyInt(i32);
+        };
+        let toks = gen_ops(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            impl std::ops::Neg for MyInt
+            where
+                i32: std::ops::Neg<Output = i32>
+            {
+                type Output = MyInt;
+
+                fn neg(self) -> Self::Output {
+                    <MyInt as opaque_typedef::OpaqueTypedefSizedInfallible>::from_inner(
+                        <i32 as std::ops::Neg>::neg(
+                            <MyInt as opaque_typedef::OpaqueTypedefSized>::into_inner(self)
+                        )
+                    )
+                }
+            }
+            impl<'__lhs> std::ops::Neg for &'__lhs MyInt
+            where
+                &'__lhs i32: std::ops::Neg<Output = i32>
+            {
+                type Output = MyInt;
+
+                fn neg(self) -> Self::Output {
+                    <MyInt as opaque_typedef::OpaqueTypedefSizedInfallible>::from_inner(
+                        <&'__lhs i32 as std::ops::Neg>::neg(
+                            <MyInt as opaque_typedef::OpaqueTypedefSized>::as_inner(self)
+                        )
+                    )
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn add_result() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(ops(add, on_invalid = "result"))]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            pub struct MyInt<T>(T);
+        };
+        let toks = gen_ops(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        // Check one of the generated impls.
+        let expected = quote! {
+            impl<'__lhs, '__rhs, T> std::ops::Add<&'__rhs MyInt<T> > for &'__lhs MyInt<T>
+            where
+                &'__lhs T: std::ops::Add<&'__rhs T, Output = T>
+            {
+                type Output = std::result::Result<
+                    MyInt<T>,
+                    <MyInt<T> as opaque_typedef::OpaqueTypedefSized>::Error
+                >;
+
+                fn add(self, rhs: &'__rhs MyInt<T>) -> Self::Output {
+                    <MyInt<T> as opaque_typedef::OpaqueTypedefSized>::try_from_inner(
+                        <&'__lhs T as std::ops::Add<&'__rhs T>>::add(
+                            <MyInt<T> as opaque_typedef::OpaqueTypedefSized>::as_inner(self),
+                            <MyInt<T> as opaque_typedef::OpaqueTypedefSized>::as_inner(rhs)
+                        )
+                    )
+                }
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.contains(&expected.to_string()), "{}", toks);
+        assert_eq!(toks.matches("impl").count(), 6);
+    }
+
+    #[test]
+    fn add_assign_validated() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(ops(add_assign))]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            pub struct MyInt(i32);
+        };
+        let toks = gen_ops(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            impl std::ops::AddAssign<i32> for MyInt
+            where
+                i32: std::ops::AddAssign<i32>,
+                i32: std::clone::Clone
+            {
+                fn add_assign(&mut self, rhs: i32) {
+                    let rhs = rhs;
+                    <MyInt as opaque_typedef::OpaqueTypedefSized>::try_modify_inner(self, |inner| {
+                        <i32 as std::ops::AddAssign<i32>>::add_assign(inner, rhs)
+                    })
+                    .expect("Invalid result of `AddAssign`")
+                }
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.contains(&expected.to_string()), "{}", toks);
+        assert_eq!(toks.matches("impl").count(), 3);
+    }
+
+    #[test]
+    fn add_assign_infallible() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+            #[opaque_typedef(ops(add_assign))]
+            pub struct MyInt<T>(T);
+        };
+        let toks = gen_ops(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            impl<'__rhs, T> std::ops::AddAssign<&'__rhs MyInt<T> > for MyInt<T>
+            where
+                T: std::ops::AddAssign<&'__rhs T>
+            {
+                fn add_assign(&mut self, rhs: &'__rhs MyInt<T>) {
+                    <T as std::ops::AddAssign<&'__rhs T>>::add_assign(
+                        &mut self.0,
+                        <MyInt<T> as opaque_typedef::OpaqueTypedefSized>::as_inner(rhs)
+                    )
+                }
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.contains(&expected.to_string()), "{}", toks);
+        assert!(!toks.contains("Clone"), "{}", toks);
+        assert_eq!(toks.matches("impl").count(), 3);
+    }
+
+    #[test]
+    fn mask() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+            #[opaque_typedef(ops(mask))]
+            pub struct Flags(u8);
+        };
+        let toks = gen_ops(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let as_inner = quote!(<Flags as opaque_typedef::OpaqueTypedefSized>::as_inner);
+        let from_inner =
+            quote!(<Flags as opaque_typedef::OpaqueTypedefSizedInfallible>::from_inner);
+        let expected = quote! {
+            impl Flags
+            where
+                for<'__a> &'__a u8: std::ops::BitAnd<&'__a u8, Output = u8>
+                    + std::ops::BitOr<&'__a u8, Output = u8>
+                    + std::ops::Not<Output = u8>,
+                u8: std::cmp::PartialEq
+            {
+                /// Returns `true` if all the bits in `other` are set in `self`.
+                pub fn contains(&self, other: &Self) -> bool {
+                    let other = #as_inner(other);
+                    (#as_inner(self) & other) == *other
+                }
+
+                /// Sets the bits in `other` to `self`.
+                pub fn insert(&mut self, other: &Self) {
+                    let inserted = #as_inner(self) | #as_inner(other);
+                    *self = #from_inner(inserted);
+                }
+
+                /// Clears the bits in `other` from `self`.
+                pub fn remove(&mut self, other: &Self) {
+                    let removed = #as_inner(self) & &!#as_inner(other);
+                    *self = #from_inner(removed);
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn shl_rhs() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+            #[opaque_typedef(ops(shl(rhs = "u32"), shl_assign(rhs = "u32")))]
+            pub struct Flags(u8);
+        };
+        let toks = gen_ops(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            impl<'__lhs> std::ops::Shl<u32> for &'__lhs Flags
+            where
+                &'__lhs u8: std::ops::Shl<u32, Output = u8>
+            {
+                type Output = Flags;
+
+                fn shl(self, rhs: u32) -> Self::Output {
+                    <Flags as opaque_typedef::OpaqueTypedefSizedInfallible>::from_inner(
+                        <&'__lhs u8 as std::ops::Shl<u32>>::shl(
+                            <Flags as opaque_typedef::OpaqueTypedefSized>::as_inner(self),
+                            rhs
+                        )
+                    )
+                }
+            }
+            impl std::ops::ShlAssign<u32> for Flags
+            where
+                u8: std::ops::ShlAssign<u32>
+            {
+                fn shl_assign(&mut self, rhs: u32) {
+                    <u8 as std::ops::ShlAssign<u32>>::shl_assign(&mut self.0, rhs)
+                }
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.contains(&expected.to_string()), "{}", toks);
+        // `Flags << u32`, `&Flags << u32`, and `Flags <<= u32`.
+        assert_eq!(toks.matches("impl").count(), 3);
+    }
+
+    #[test]
+    fn mask_result() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(ops(mask, on_invalid = "result"))]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            pub struct Flags(u8);
+        };
+        let toks = gen_ops(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let base_trait = quote!(opaque_typedef::OpaqueTypedefSized);
+        let expected = quote! {
+            /// Sets the bits in `other` to `self`.
+            pub fn insert(
+                &mut self,
+                other: &Self
+            ) -> std::result::Result<(), <Flags as #base_trait>::Error> {
+                let inserted = <Flags as #base_trait>::as_inner(self)
+                    | <Flags as #base_trait>::as_inner(other);
+                *self = <Flags as #base_trait>::try_from_inner(inserted)?;
+                Ok(())
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.contains(&expected.to_string()), "{}", toks);
+    }
+
+    #[test]
+    fn assign_with_result() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(ops(add_assign, on_invalid = "result"))]
+            pub struct MyInt(i32);
+        };
+        assert!(gen_ops(&Input::new(&input).unwrap(), Kind::Sized).is_err());
+    }
+
+    #[test]
+    fn unsized_type() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(ops(add))]
+            pub struct MyStr(str);
+        };
+        assert!(gen_ops(&Input::new(&input).unwrap(), Kind::Unsized).is_err());
+    }
+}
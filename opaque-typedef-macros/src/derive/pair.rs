@@ -0,0 +1,347 @@
+//! Codegen for the pair of an owned type and a borrowed type.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, WherePredicate};
+
+use crate::{
+    derive::{
+        cmp_with::{gen_cmp_pair, CmpImpl},
+        Kind,
+    },
+    input::Input,
+};
+
+/// Generate impls to link an owned type and a borrowed type.
+///
+/// `owned = ..` is available for unsized types, and `borrowed = ..` is available for sized types.
+/// Both generate the same set of impls: `OpaqueTypedefOwned`, `Deref`, `Borrow`, `AsRef`,
+/// `ToOwned`, and comparisons between the two types.
+///
+/// `OpaqueTypedefOwned` requires both types to have the same validation, which cannot be checked
+/// by the derive.
+/// Without `unsafe_pair`, the impl is bounded on both types having infallible conversions, and
+/// types with a validator are rejected.
+pub fn gen_pair(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    let pair = match (input.pair(), input.unsafe_pair()) {
+        (Some(v), _) => v,
+        (None, Some(span)) => {
+            return Err(syn::Error::new(
+                span,
+                "`unsafe_pair` requires `owned = ..` or `borrowed = ..`",
+            ))
+        }
+        (None, None) => return Ok(TokenStream::new()),
+    };
+    if let (Some(validator), None) = (input.validator(), input.unsafe_pair()) {
+        return Err(syn::Error::new(
+            validator.span(),
+            format!(
+                "`{}` for a type with validator requires `#[opaque_typedef(unsafe_pair)]`, \
+                 which asserts that both types have the same validation",
+                pair.key()
+            ),
+        ));
+    }
+    let ty = input.ident();
+    let (_, generics_ty, _) = input.generics().split_for_impl();
+    let ty_self = quote!(#ty #generics_ty);
+    let ty_other = pair.ty();
+    let ty_field = input.primary_field().ty();
+    let sized = Kind::Sized.base_trait();
+    let unsized_ = Kind::Unsized.base_trait();
+    let (ty_owned, ty_borrowed, ty_owned_inner, ty_borrowed_inner) = match (kind, pair.key()) {
+        (Kind::Sized, key) if key == "borrowed" => (
+            ty_self,
+            quote!(#ty_other),
+            quote!(#ty_field),
+            quote!(<#ty_other as opaque_typedef::TransparentUnsized>::Inner),
+        ),
+        (Kind::Unsized, key) if key == "owned" => (
+            quote!(#ty_other),
+            ty_self,
+            quote!(<#ty_other as #sized>::Inner),
+            quote!(#ty_field),
+        ),
+        (_, key) => {
+            return Err(syn::Error::new(
+                key.span(),
+                format!(
+                    "`{}` is not available for `{}` types",
+                    key,
+                    match kind {
+                        Kind::Sized => "OpaqueTypedefSized",
+                        Kind::Unsized => "OpaqueTypedefUnsized",
+                    }
+                ),
+            ))
+        }
+    };
+    let owned_trait = quote!(opaque_typedef::OpaqueTypedefOwned);
+    let pred_borrow: WherePredicate =
+        syn::parse_quote!(#ty_owned_inner: std::borrow::Borrow<#ty_borrowed_inner>);
+    let pred_to_owned: WherePredicate =
+        syn::parse_quote!(#ty_borrowed_inner: std::borrow::ToOwned<Owned = #ty_owned_inner>);
+    let with_predicates = |trait_name: &str, predicates: Vec<WherePredicate>| {
+        let generics = input.generics_for(trait_name, predicates);
+        let (generics_impl, _, generics_where) = generics.split_for_impl();
+        (quote!(#generics_impl), quote!(#generics_where))
+    };
+    let mut generics = input.generics_for("OpaqueTypedefOwned", vec![]);
+    if input.unsafe_pair().is_none() {
+        // Any inner value is valid for both types, so the conversions cannot break the
+        // invariants.
+        let predicates = &mut generics.make_where_clause().predicates;
+        predicates.push(syn::parse_quote! {
+            #ty_owned: #sized<Error = std::convert::Infallible>
+        });
+        predicates.push(syn::parse_quote! {
+            #ty_borrowed: opaque_typedef::TransparentUnsized<Error = std::convert::Infallible>
+        });
+    }
+    let (generics_impl, _, generics_where) = generics.split_for_impl();
+    let (generics_impl_deref, generics_where_deref) =
+        with_predicates("Deref", vec![pred_borrow.clone()]);
+    let (generics_impl_borrow, generics_where_borrow) =
+        with_predicates("Borrow", vec![pred_borrow.clone()]);
+    let (generics_impl_as_ref, generics_where_as_ref) =
+        with_predicates("AsRef", vec![pred_borrow.clone()]);
+    let (generics_impl_to_owned, generics_where_to_owned) =
+        with_predicates("ToOwned", vec![pred_borrow.clone(), pred_to_owned]);
+    let as_borrowed = quote!(<#ty_owned as #owned_trait>::as_borrowed);
+
+    let mut toks = quote! {
+        unsafe impl #generics_impl #owned_trait for #ty_owned #generics_where {
+            type Borrowed = #ty_borrowed;
+        }
+
+        impl #generics_impl_deref std::ops::Deref for #ty_owned #generics_where_deref {
+            type Target = #ty_borrowed;
+
+            fn deref(&self) -> &Self::Target {
+                #as_borrowed(self)
+            }
+        }
+
+        impl #generics_impl_borrow std::borrow::Borrow<#ty_borrowed> for #ty_owned
+            #generics_where_borrow
+        {
+            fn borrow(&self) -> &#ty_borrowed {
+                #as_borrowed(self)
+            }
+        }
+
+        impl #generics_impl_as_ref std::convert::AsRef<#ty_borrowed> for #ty_owned
+            #generics_where_as_ref
+        {
+            fn as_ref(&self) -> &#ty_borrowed {
+                #as_borrowed(self)
+            }
+        }
+
+        impl #generics_impl_to_owned std::borrow::ToOwned for #ty_borrowed
+            #generics_where_to_owned
+        {
+            type Owned = #ty_owned;
+
+            fn to_owned(&self) -> Self::Owned {
+                <#ty_owned as #owned_trait>::from_borrowed(self)
+            }
+        }
+    };
+
+    // Compare the values as the inner type of the borrowed type.
+    let borrowed_inner = |expr: TokenStream| quote!(<#ty_borrowed as #unsized_>::as_inner(#expr));
+    let owned_to_base = borrowed_inner(quote!(#as_borrowed(outer)));
+    let cmp_impls = gen_cmp_pair(
+        (&ty_owned, &owned_to_base),
+        (&ty_borrowed, &borrowed_inner(quote!(target))),
+        &ty_borrowed_inner,
+    )
+    .into_iter()
+    .chain(gen_cmp_pair(
+        (&ty_owned, &owned_to_base),
+        (&quote!(&'_ #ty_borrowed), &borrowed_inner(quote!(*target))),
+        &ty_borrowed_inner,
+    ));
+    for CmpImpl {
+        trait_name,
+        trait_,
+        ty_trait,
+        ty_self,
+        body,
+    } in cmp_impls
+    {
+        let (generics_impl, generics_where) = with_predicates(
+            trait_name,
+            vec![
+                pred_borrow.clone(),
+                syn::parse_quote!(#ty_borrowed_inner: #trait_),
+            ],
+        );
+        toks.extend(quote! {
+            impl #generics_impl #ty_trait for #ty_self #generics_where {
+                #body
+            }
+        });
+    }
+
+    Ok(toks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrowed() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(borrowed = "MyStr")]
+            pub struct MyString(String);
+        };
+        let toks = gen_pair(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let borrowed_inner = quote!(<MyStr as opaque_typedef::TransparentUnsized>::Inner);
+        let as_borrowed = quote!(<MyString as opaque_typedef::OpaqueTypedefOwned>::as_borrowed);
+        let expected = quote! {
+            unsafe impl opaque_typedef::OpaqueTypedefOwned for MyString
+            where
+                MyString: opaque_typedef::OpaqueTypedefSized<Error = std::convert::Infallible>,
+                MyStr: opaque_typedef::TransparentUnsized<Error = std::convert::Infallible>
+            {
+                type Borrowed = MyStr;
+            }
+
+            impl std::ops::Deref for MyString
+            where
+                String: std::borrow::Borrow<#borrowed_inner>
+            {
+                type Target = MyStr;
+
+                fn deref(&self) -> &Self::Target {
+                    #as_borrowed(self)
+                }
+            }
+
+            impl std::borrow::Borrow<MyStr> for MyString
+            where
+                String: std::borrow::Borrow<#borrowed_inner>
+            {
+                fn borrow(&self) -> &MyStr {
+                    #as_borrowed(self)
+                }
+            }
+
+            impl std::convert::AsRef<MyStr> for MyString
+            where
+                String: std::borrow::Borrow<#borrowed_inner>
+            {
+                fn as_ref(&self) -> &MyStr {
+                    #as_borrowed(self)
+                }
+            }
+
+            impl std::borrow::ToOwned for MyStr
+            where
+                String: std::borrow::Borrow<#borrowed_inner>,
+                #borrowed_inner: std::borrow::ToOwned<Owned = String>
+            {
+                type Owned = MyString;
+
+                fn to_owned(&self) -> Self::Owned {
+                    <MyString as opaque_typedef::OpaqueTypedefOwned>::from_borrowed(self)
+                }
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.starts_with(&expected.to_string()), "{}", toks);
+        // 4 comparison impls for each of `MyStr` and `&MyStr`.
+        assert_eq!(toks.matches("impl").count(), 5 + 8);
+    }
+
+    #[test]
+    fn owned() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(owned = "MyString")]
+            pub struct MyStr(str);
+        };
+        let toks = gen_pair(&Input::new(&input).unwrap(), Kind::Unsized).unwrap();
+        let expected = quote! {
+            unsafe impl opaque_typedef::OpaqueTypedefOwned for MyString
+            where
+                MyString: opaque_typedef::OpaqueTypedefSized<Error = std::convert::Infallible>,
+                MyStr: opaque_typedef::TransparentUnsized<Error = std::convert::Infallible>
+            {
+                type Borrowed = MyStr;
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.starts_with(&expected.to_string()), "{}", toks);
+        let expected = quote! {
+            impl std::cmp::PartialOrd<&'_ MyStr> for MyString
+            where
+                <MyString as opaque_typedef::OpaqueTypedefSized>::Inner: std::borrow::Borrow<str>,
+                str: std::cmp::PartialOrd
+            {
+                fn partial_cmp(&self, other: & &'_ MyStr) -> std::option::Option<std::cmp::Ordering> {
+                    let (outer, target) = (self, other);
+                    <str as std::cmp::PartialOrd>::partial_cmp(
+                        <MyStr as opaque_typedef::OpaqueTypedefUnsized>::as_inner(
+                            <MyString as opaque_typedef::OpaqueTypedefOwned>::as_borrowed(outer)
+                        ),
+                        <MyStr as opaque_typedef::OpaqueTypedefUnsized>::as_inner(*target)
+                    )
+                }
+            }
+        };
+        assert!(toks.contains(&expected.to_string()), "{}", toks);
+    }
+
+    #[test]
+    fn validated_without_unsafe_pair() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            #[opaque_typedef(borrowed = "AsciiStr")]
+            pub struct Unchecked(String);
+        };
+        assert!(gen_pair(&Input::new(&input).unwrap(), Kind::Sized).is_err());
+    }
+
+    #[test]
+    fn unsafe_pair() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            #[opaque_typedef(borrowed = "AsciiStr", unsafe_pair)]
+            pub struct AsciiString(String);
+        };
+        let toks = gen_pair(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let expected = quote! {
+            unsafe impl opaque_typedef::OpaqueTypedefOwned for AsciiString {
+                type Borrowed = AsciiStr;
+            }
+        };
+        let toks = toks.to_string();
+        assert!(toks.starts_with(&expected.to_string()), "{}", toks);
+
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(unsafe_pair)]
+            pub struct AsciiString(String);
+        };
+        assert!(gen_pair(&Input::new(&input).unwrap(), Kind::Sized).is_err());
+    }
+
+    #[test]
+    fn wrong_kind() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(owned = "MyString")]
+            pub struct MyStr(Box<str>);
+        };
+        assert!(gen_pair(&Input::new(&input).unwrap(), Kind::Sized).is_err());
+    }
+}
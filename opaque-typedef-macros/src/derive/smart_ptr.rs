@@ -0,0 +1,128 @@
+//! Smart pointers codegen for unsized types.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{attr::SmartPtr, derive::Kind, input::Input};
+
+/// Generate impls for smart pointers to the type.
+///
+/// For each smart pointer `P`, `From<&Outer> for P<Outer>` is generated.
+/// For `Box`, `Clone for Box<Outer>` is also generated.
+/// The values are copied by the conversion of the inner type, e.g. `From<&str> for Box<str>`.
+pub fn gen_smart_ptr(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    let span = match input.smart_ptr().span() {
+        Some(v) => v,
+        None => return Ok(TokenStream::new()),
+    };
+    if kind != Kind::Unsized {
+        return Err(syn::Error::new(
+            span,
+            "`smart_ptr` is only available for `OpaqueTypedefUnsized` types",
+        ));
+    }
+
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let (_, generics_ty, _) = input.generics().split_for_impl();
+    let ty_outer = quote!(#ty #generics_ty);
+
+    let mut toks = TokenStream::new();
+    for ptr in input.smart_ptr().ptrs() {
+        let (ty_ptr, from_ref) = match ptr {
+            SmartPtr::Box => (quote!(std::boxed::Box), quote!(box_from_ref)),
+            SmartPtr::Rc => (quote!(std::rc::Rc), quote!(rc_from_ref)),
+            SmartPtr::Arc => (quote!(std::sync::Arc), quote!(arc_from_ref)),
+        };
+
+        let mut generics = input.generics_for(
+            "From",
+            Some(syn::parse_quote! {
+                #ty_ptr<#ty_inner>: std::convert::From<&'__a #ty_inner>
+            }),
+        );
+        generics.params.insert(0, syn::parse_quote!('__a));
+        let (generics_impl, _, generics_where) = generics.split_for_impl();
+        toks.extend(quote! {
+            impl #generics_impl std::convert::From<&'__a #ty_outer> for #ty_ptr<#ty_outer>
+                #generics_where
+            {
+                fn from(outer: &'__a #ty_outer) -> Self {
+                    opaque_typedef::cast::#from_ref(outer)
+                }
+            }
+        });
+
+        if let SmartPtr::Box = ptr {
+            let generics = input.generics_for(
+                "Clone",
+                Some(syn::parse_quote! {
+                    for<'__a> #ty_ptr<#ty_inner>: std::convert::From<&'__a #ty_inner>
+                }),
+            );
+            let (generics_impl, _, generics_where) = generics.split_for_impl();
+            toks.extend(quote! {
+                impl #generics_impl std::clone::Clone for #ty_ptr<#ty_outer> #generics_where {
+                    fn clone(&self) -> Self {
+                        opaque_typedef::cast::#from_ref(&**self)
+                    }
+                }
+            });
+        }
+    }
+
+    Ok(toks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_rc() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(smart_ptr(box, rc))]
+            pub struct MyStr(str);
+        };
+        let toks = gen_smart_ptr(&Input::new(&input).unwrap(), Kind::Unsized).unwrap();
+        let expected = quote! {
+            impl<'__a> std::convert::From<&'__a MyStr> for std::boxed::Box<MyStr>
+            where
+                std::boxed::Box<str>: std::convert::From<&'__a str>
+            {
+                fn from(outer: &'__a MyStr) -> Self {
+                    opaque_typedef::cast::box_from_ref(outer)
+                }
+            }
+            impl std::clone::Clone for std::boxed::Box<MyStr>
+            where
+                for<'__a> std::boxed::Box<str>: std::convert::From<&'__a str>
+            {
+                fn clone(&self) -> Self {
+                    opaque_typedef::cast::box_from_ref(&**self)
+                }
+            }
+            impl<'__a> std::convert::From<&'__a MyStr> for std::rc::Rc<MyStr>
+            where
+                std::rc::Rc<str>: std::convert::From<&'__a str>
+            {
+                fn from(outer: &'__a MyStr) -> Self {
+                    opaque_typedef::cast::rc_from_ref(outer)
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn sized_type() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(smart_ptr(box))]
+            pub struct MyString(String);
+        };
+        assert!(gen_smart_ptr(&Input::new(&input).unwrap(), Kind::Sized).is_err());
+    }
+}
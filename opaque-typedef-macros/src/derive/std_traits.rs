@@ -0,0 +1,278 @@
+//! Codegen for std traits derived by `derive(..)`.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Ident;
+
+use crate::{
+    derive::Kind,
+    input::{FieldAccessor, Input},
+};
+
+/// Generate impls for std traits.
+///
+/// Unlike the derive macros in std, the impls are bounded only on the inner type, so the fields
+/// other than the inner field (such as tags) are treated as markers.
+pub fn gen_std_traits(input: &Input, kind: Kind) -> syn::Result<TokenStream> {
+    ensure_no_conflicts(input, kind)?;
+
+    let ty = input.ident();
+    let ty_inner = input.primary_field().ty();
+    let base_trait = kind.base_trait();
+    let as_inner = |expr: TokenStream| quote!(<Self as #base_trait>::as_inner(#expr));
+    let (inner_self, inner_other) = (as_inner(quote!(self)), as_inner(quote!(other)));
+
+    let impls = input.derive().traits().map(|(name, module)| {
+        let trait_ = Ident::new(name, Span::call_site());
+        let module = Ident::new(module, Span::call_site());
+        let trait_path = quote!(std::#module::#trait_);
+        let items = match name {
+            "Clone" => {
+                let expr = input.construct(quote!(#trait_path::clone(#inner_self)));
+                quote! {
+                    fn clone(&self) -> Self {
+                        #expr
+                    }
+                }
+            }
+            "Copy" | "Eq" => quote!(),
+            "PartialEq" => quote! {
+                fn eq(&self, other: &Self) -> bool {
+                    #trait_path::eq(#inner_self, #inner_other)
+                }
+            },
+            "PartialOrd" => quote! {
+                fn partial_cmp(&self, other: &Self) -> std::option::Option<std::cmp::Ordering> {
+                    #trait_path::partial_cmp(#inner_self, #inner_other)
+                }
+            },
+            "Ord" => quote! {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    #trait_path::cmp(#inner_self, #inner_other)
+                }
+            },
+            "Hash" => quote! {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    #trait_path::hash(#inner_self, state)
+                }
+            },
+            "Debug" => {
+                let name = ty.to_string();
+                let body = match input.primary_field().accessor() {
+                    FieldAccessor::Named(ident) => {
+                        let field = ident.to_string();
+                        quote!(f.debug_struct(#name).field(#field, &#inner_self).finish())
+                    }
+                    FieldAccessor::Unnamed(_) => {
+                        quote!(f.debug_tuple(#name).field(&#inner_self).finish())
+                    }
+                };
+                quote! {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        #body
+                    }
+                }
+            }
+            _ => unreachable!("Should be rejected on parsing"),
+        };
+
+        let generics = input.generics_for(name, Some(syn::parse_quote!(#ty_inner: #trait_path)));
+        let (generics_impl, generics_ty, generics_where) = generics.split_for_impl();
+        quote! {
+            impl #generics_impl #trait_path for #ty #generics_ty #generics_where {
+                #items
+            }
+        }
+    });
+
+    Ok(quote!(#(#impls)*))
+}
+
+/// Ensures the derived traits are available and not implemented by other attributes.
+fn ensure_no_conflicts(input: &Input, kind: Kind) -> syn::Result<()> {
+    let derive = input.derive();
+    if kind == Kind::Unsized {
+        for name in &["Clone", "Copy"] {
+            if let Some(span) = derive.get(name) {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "`{}` is not available for `OpaqueTypedefUnsized` types",
+                        name
+                    ),
+                ));
+            }
+        }
+    }
+    if input.cmp().is_some() {
+        for name in &["PartialEq", "Eq", "PartialOrd", "Ord", "Hash"] {
+            if let Some(span) = derive.get(name) {
+                return Err(syn::Error::new(
+                    span,
+                    format!("`{}` is already implemented by `cmp(..)`", name),
+                ));
+            }
+        }
+    }
+    if input.fmt().debug().is_some() {
+        if let Some(span) = derive.get("Debug") {
+            return Err(syn::Error::new(
+                span,
+                "`Debug` is already implemented by `fmt(debug = ..)`",
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tagged() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(derive(Clone, Copy, PartialEq, Hash, Debug))]
+            pub struct Id<T, Tag> {
+                #[opaque_typedef(inner)]
+                id: T,
+                tag: PhantomData<Tag>,
+            }
+        };
+        let toks = gen_std_traits(&Input::new(&input).unwrap(), Kind::Sized).unwrap();
+        let as_inner = quote!(<Self as opaque_typedef::OpaqueTypedefSized>::as_inner);
+        let expected = quote! {
+            impl<T, Tag> std::clone::Clone for Id<T, Tag>
+            where
+                T: std::clone::Clone
+            {
+                fn clone(&self) -> Self {
+                    Self {
+                        id: std::clone::Clone::clone(#as_inner(self)),
+                        tag: std::marker::PhantomData,
+                    }
+                }
+            }
+            impl<T, Tag> std::marker::Copy for Id<T, Tag>
+            where
+                T: std::marker::Copy
+            {}
+            impl<T, Tag> std::cmp::PartialEq for Id<T, Tag>
+            where
+                T: std::cmp::PartialEq
+            {
+                fn eq(&self, other: &Self) -> bool {
+                    std::cmp::PartialEq::eq(#as_inner(self), #as_inner(other))
+                }
+            }
+            impl<T, Tag> std::hash::Hash for Id<T, Tag>
+            where
+                T: std::hash::Hash
+            {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    std::hash::Hash::hash(#as_inner(self), state)
+                }
+            }
+            impl<T, Tag> std::fmt::Debug for Id<T, Tag>
+            where
+                T: std::fmt::Debug
+            {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.debug_struct("Id").field("id", &#as_inner(self)).finish()
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn unsized_ord() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(derive(Eq, PartialOrd, Ord))]
+            pub struct MyStr(str);
+        };
+        let toks = gen_std_traits(&Input::new(&input).unwrap(), Kind::Unsized).unwrap();
+        let as_inner = quote!(<Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner);
+        let expected = quote! {
+            impl std::cmp::Eq for MyStr
+            where
+                str: std::cmp::Eq
+            {}
+            impl std::cmp::PartialOrd for MyStr
+            where
+                str: std::cmp::PartialOrd
+            {
+                fn partial_cmp(&self, other: &Self) -> std::option::Option<std::cmp::Ordering> {
+                    std::cmp::PartialOrd::partial_cmp(#as_inner(self), #as_inner(other))
+                }
+            }
+            impl std::cmp::Ord for MyStr
+            where
+                str: std::cmp::Ord
+            {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    std::cmp::Ord::cmp(#as_inner(self), #as_inner(other))
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn bound() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(derive(Eq, Debug))]
+            #[opaque_typedef(bound = "T: Ord", bound(Debug = "T: Debug"))]
+            pub struct MySlice<T>([T]);
+        };
+        let toks = gen_std_traits(&Input::new(&input).unwrap(), Kind::Unsized).unwrap();
+        let as_inner = quote!(<Self as opaque_typedef::OpaqueTypedefUnsized>::as_inner);
+        let expected = quote! {
+            impl<T> std::cmp::Eq for MySlice<T>
+            where
+                [T]: std::cmp::Eq,
+                T: Ord
+            {}
+            impl<T> std::fmt::Debug for MySlice<T>
+            where
+                T: Debug,
+                T: Ord
+            {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.debug_tuple("MySlice").field(&#as_inner(self)).finish()
+                }
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn conflicts() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefUnsized)]
+            #[repr(transparent)]
+            #[opaque_typedef(derive(Clone))]
+            pub struct MyStr(str);
+        };
+        assert!(gen_std_traits(&Input::new(&input).unwrap(), Kind::Unsized).is_err());
+
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(derive(Hash), cmp(ascii_case_insensitive))]
+            pub struct MyString(String);
+        };
+        assert!(gen_std_traits(&Input::new(&input).unwrap(), Kind::Sized).is_err());
+
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(derive(Debug), fmt(debug = transparent))]
+            pub struct MyString(String);
+        };
+        assert!(gen_std_traits(&Input::new(&input).unwrap(), Kind::Sized).is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! Error helpers.
+
+/// Accumulator of errors, to report multiple errors at once.
+#[derive(Default)]
+pub struct Errors {
+    /// Combined error.
+    error: Option<syn::Error>,
+}
+
+impl Errors {
+    /// Creates a new empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the given error.
+    pub fn push(&mut self, error: syn::Error) {
+        match &mut self.error {
+            Some(combined) => combined.combine(error),
+            None => self.error = Some(error),
+        }
+    }
+
+    /// Returns the value if the given result is `Ok`, or adds the error and returns `None`.
+    pub fn collect<T>(&mut self, result: syn::Result<T>) -> Option<T> {
+        match result {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.push(e);
+                None
+            }
+        }
+    }
+
+    /// Returns `Err` if any errors are added.
+    pub fn finish(self) -> syn::Result<()> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
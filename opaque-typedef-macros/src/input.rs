@@ -1,58 +1,127 @@
 //! Input data.
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{Data, DeriveInput, Expr, Field, Fields, Ident, Meta, Type};
+use syn::{
+    spanned::Spanned, Data, DeriveInput, Expr, Field, Fields, Generics, Ident, Meta, Type,
+    WherePredicate,
+};
 
-use crate::attr::MetaExt;
+use crate::{
+    attr::{
+        bound_trait_names, CmpKey, ConvertAttr, DerefAttr, DeriveAttr, FieldAttrs, FmtAttr,
+        GetterAttr, MetaExt, OpsAttr, PairAttr, RefTarget, SmartPtrAttr, TypeAttrs,
+    },
+    error::Errors,
+};
 
 /// Input data.
 ///
 /// *Primary field* is the field which has data convertible from and into the inner type.
+/// Composite inputs have one or more primary fields, and the inner type is the tuple of them.
 pub struct Input<'a> {
     /// Derive input.
     derive_input: &'a DeriveInput,
-    /// Primary field.
-    primary_field: FieldWrapper<'a>,
-    /// Whether to hide the base traits impls docs.
-    should_hide_base_impl_docs: bool,
-    /// Attributes parsed as meta.
-    meta_attrs: Vec<Meta>,
-    /// Validator.
-    validator: Option<Expr>,
-    /// Validation error type.
-    ty_validation_error: Option<Type>,
+    /// Fields.
+    fields: Vec<FieldWrapper<'a>>,
+    /// Indices of the primary fields.
+    primary_field_indices: Vec<usize>,
+    /// Type-level attributes.
+    attrs: TypeAttrs,
+    /// `repr` attributes parsed as meta.
+    repr_attrs: Vec<Meta>,
 }
 
 impl<'a> Input<'a> {
     /// Creates an `Input` form the given `DeriveInput`.
     pub fn new(derive_input: &'a DeriveInput) -> Result<Self, syn::Error> {
-        let primary_field = get_primary_field(fields(&derive_input.data));
-        let meta_attrs = derive_input
+        Self::with_composite_flag(derive_input, false)
+    }
+
+    /// Creates an `Input` with the composite inner form the given `DeriveInput`.
+    pub fn new_composite(derive_input: &'a DeriveInput) -> Result<Self, syn::Error> {
+        Self::with_composite_flag(derive_input, true)
+    }
+
+    /// Creates an `Input` form the given `DeriveInput`.
+    ///
+    /// If `composite` is true, all fields marked by `#[opaque_typedef(inner)]` are primary.
+    fn with_composite_flag(
+        derive_input: &'a DeriveInput,
+        composite: bool,
+    ) -> Result<Self, syn::Error> {
+        let mut errors = Errors::new();
+        let raw_fields = errors.collect(struct_fields(derive_input));
+        let fields = raw_fields.and_then(|raw_fields| {
+            // Parse attributes of all fields first, so that all errors are reported.
+            let fields = raw_fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    let attrs = errors.collect(FieldAttrs::from_attrs(&field.attrs))?;
+                    Some(FieldWrapper::new(i, field, attrs))
+                })
+                .collect::<Vec<_>>();
+            fields.into_iter().collect::<Option<Vec<_>>>()
+        });
+        // The primary field can be determined only when all field attributes are valid.
+        let primary_field_indices = match (raw_fields, &fields) {
+            (Some(raw_fields), Some(fields)) if composite => errors.collect(
+                get_composite_field_indices(&derive_input.ident, raw_fields, fields),
+            ),
+            (Some(raw_fields), Some(fields)) => errors
+                .collect(get_primary_field_index(
+                    &derive_input.ident,
+                    raw_fields,
+                    fields,
+                ))
+                .map(|index| vec![index]),
+            _ => None,
+        };
+        // The inner field is initialized by the inner value, so it cannot have an initializer.
+        if let (Some(fields), Some(indices)) = (&fields, &primary_field_indices) {
+            for &index in indices {
+                if let Some(init) = fields[index].attrs.init() {
+                    errors.push(syn::Error::new_spanned(
+                        init,
+                        "`init` is not available for the inner field",
+                    ));
+                }
+            }
+            // Getters replace `as_inner()` only for composite types.
+            for field in fields {
+                if let Some(getter) = field.attrs.getter() {
+                    if !composite || !indices.contains(&field.index()) {
+                        errors.push(syn::Error::new(
+                            getter.span(),
+                            "`getter` is available only for the inner fields of composite types",
+                        ));
+                    }
+                }
+            }
+        }
+        let attrs = errors.collect(TypeAttrs::from_attrs(&derive_input.attrs));
+        if let (Some(attrs), true) = (&attrs, composite) {
+            errors.collect(attrs.ensure_composite_compatible());
+        }
+        errors.finish()?;
+        let repr_attrs = derive_input
             .attrs
             .iter()
+            .filter(|attr| attr.path.is_ident("repr"))
             .flat_map(|attr| attr.parse_meta())
             .collect::<Vec<_>>();
-        let should_hide_base_impl_docs = meta_attrs
-            .iter()
-            .any(|meta| meta.has_level2_word("opaque_typedef", "hide_base_impl_docs"));
-        let validator = meta_attrs
-            .iter()
-            .find_map(|attr| attr.validator().transpose())
-            .transpose()?;
-        let ty_validation_error = meta_attrs
-            .iter()
-            .find_map(|attr| attr.ty_validation_error().transpose())
-            .transpose()?;
-
-        Ok(Self {
-            derive_input,
-            primary_field,
-            should_hide_base_impl_docs,
-            meta_attrs,
-            validator,
-            ty_validation_error,
-        })
+
+        match (fields, primary_field_indices, attrs) {
+            (Some(fields), Some(primary_field_indices), Some(attrs)) => Ok(Self {
+                derive_input,
+                fields,
+                primary_field_indices,
+                attrs,
+                repr_attrs,
+            }),
+            _ => unreachable!("Should be available when no errors are reported"),
+        }
     }
 
     /// Returns the identifier of the type.
@@ -60,34 +129,59 @@ impl<'a> Input<'a> {
         &self.derive_input.ident
     }
 
-    /// Returns raw fields.
-    fn raw_fields(&self) -> &'a Fields {
-        match &self.derive_input.data {
-            Data::Struct(v) => &v.fields,
-            _ => panic!("Only struct type is supported"),
-        }
-    }
-
     /// Returns an iterator of fields.
-    pub fn fields<'b>(&'b self) -> impl Iterator<Item = FieldWrapper<'a>> + 'b {
-        self.raw_fields()
-            .iter()
-            .enumerate()
-            .map(|(i, field)| FieldWrapper::new(i, field))
+    pub fn fields(&self) -> impl Iterator<Item = &FieldWrapper<'a>> {
+        self.fields.iter()
     }
 
     /// Returns an iterator of fields.
-    pub fn fields_with_primary_flag<'b>(
-        &'b self,
-    ) -> impl Iterator<Item = (bool, FieldWrapper<'a>)> + 'b {
-        let primary_field_index = self.primary_field.index();
+    pub fn fields_with_primary_flag(&self) -> impl Iterator<Item = (bool, &FieldWrapper<'a>)> {
+        let indices = &self.primary_field_indices;
         self.fields()
-            .map(move |field| (field.index() == primary_field_index, field))
+            .map(move |field| (indices.contains(&field.index()), field))
     }
 
     /// Returns the primary field.
+    ///
+    /// For composite inputs, this returns the first primary field.
     pub fn primary_field(&self) -> &FieldWrapper<'a> {
-        &self.primary_field
+        &self.fields[self.primary_field_indices[0]]
+    }
+
+    /// Returns an iterator of the primary fields.
+    pub fn primary_fields(&self) -> impl Iterator<Item = &FieldWrapper<'a>> {
+        self.primary_field_indices
+            .iter()
+            .map(move |&index| &self.fields[index])
+    }
+
+    /// Returns the expression to construct the value from the given inner value.
+    ///
+    /// The fields other than the inner field are initialized by their initializers.
+    pub fn construct(&self, expr_inner: TokenStream) -> TokenStream {
+        self.construct_composite(vec![expr_inner])
+    }
+
+    /// Returns the expression to construct the value from the given values of primary fields.
+    ///
+    /// `exprs_inner` should be in the same order as the primary fields.
+    pub fn construct_composite(&self, exprs_inner: Vec<TokenStream>) -> TokenStream {
+        let mut exprs_inner = exprs_inner.into_iter();
+        let init_fields = self.fields_with_primary_flag().map(|(is_primary, field)| {
+            let accessor = field.accessor();
+            if is_primary {
+                let expr_inner = exprs_inner
+                    .next()
+                    .expect("Should be given for all primary fields");
+                quote!(#accessor: #expr_inner)
+            } else {
+                let init = field.initializer();
+                quote!(#accessor: #init)
+            }
+        });
+        quote!(Self {
+            #(#init_fields,)*
+        })
     }
 
     /// Returns the generics.
@@ -95,14 +189,55 @@ impl<'a> Input<'a> {
         &self.derive_input.generics
     }
 
+    /// Returns the generics with the given predicates added to the where clause.
+    pub fn generics_with_predicates(
+        &self,
+        predicates: impl IntoIterator<Item = WherePredicate>,
+    ) -> Generics {
+        let mut generics = self.generics().clone();
+        generics.make_where_clause().predicates.extend(predicates);
+        generics
+    }
+
+    /// Returns the generics for the impl of the given trait.
+    ///
+    /// `predicates` are the bounds the impl requires, and they are replaced by
+    /// `bound(Trait = "..")` for the trait if specified. `bound = ".."` is always added.
+    pub fn generics_for(
+        &self,
+        trait_name: &str,
+        predicates: impl IntoIterator<Item = WherePredicate>,
+    ) -> Generics {
+        debug_assert!(
+            bound_trait_names().contains(&trait_name),
+            "`{}` should be available in `bound(..)`",
+            trait_name
+        );
+        match self.attrs.bound().for_trait(trait_name) {
+            Some(custom) => self.generics_for_inherent(custom.iter().cloned()),
+            None => self.generics_for_inherent(predicates),
+        }
+    }
+
+    /// Returns the generics for the inherent impl.
+    ///
+    /// `predicates` are the bounds the impl requires, and they are extended by `bound = ".."`.
+    pub fn generics_for_inherent(
+        &self,
+        predicates: impl IntoIterator<Item = WherePredicate>,
+    ) -> Generics {
+        let common = self.attrs.bound().common().iter().cloned();
+        self.generics_with_predicates(predicates.into_iter().chain(common))
+    }
+
     /// Returns the validator if available.
     pub fn validator(&self) -> Option<&Expr> {
-        self.validator.as_ref()
+        self.attrs.validator()
     }
 
     /// Returns the error type if available.
     pub fn ty_error(&self) -> Option<&Type> {
-        self.ty_validation_error.as_ref()
+        self.attrs.ty_validation_error()
     }
 
     /// Returns the error type.
@@ -113,9 +248,102 @@ impl<'a> Input<'a> {
         }
     }
 
+    /// Returns the targets of `AsRef` impls.
+    pub fn as_ref_targets(&self) -> &[RefTarget] {
+        self.attrs.as_ref_targets()
+    }
+
+    /// Returns the targets of `AsMut` impls.
+    pub fn as_mut_targets(&self) -> &[RefTarget] {
+        self.attrs.as_mut_targets()
+    }
+
+    /// Returns `cmp(..)` attribute if available.
+    pub fn cmp(&self) -> Option<&CmpKey> {
+        self.attrs.cmp()
+    }
+
+    /// Returns the targets of `cmp_with(..)`.
+    pub fn cmp_with_targets(&self) -> &[RefTarget] {
+        self.attrs.cmp_with_targets()
+    }
+
+    /// Returns the base type of the comparison by `cmp_with(..)` if specified.
+    pub fn cmp_with_base(&self) -> Option<&Type> {
+        self.attrs.cmp_with_base()
+    }
+
+    /// Returns `derive(..)` attribute.
+    pub fn derive(&self) -> &DeriveAttr {
+        self.attrs.derive()
+    }
+
+    /// Returns `fmt(..)` attribute.
+    pub fn fmt(&self) -> &FmtAttr {
+        self.attrs.fmt()
+    }
+
+    /// Returns the span of `from_str` attribute if specified.
+    pub fn impl_from_str(&self) -> Option<Span> {
+        self.attrs.impl_from_str()
+    }
+
+    /// Returns `convert(..)` attribute.
+    pub fn convert(&self) -> &ConvertAttr {
+        self.attrs.convert()
+    }
+
+    /// Returns `smart_ptr(..)` attribute.
+    pub fn smart_ptr(&self) -> &SmartPtrAttr {
+        self.attrs.smart_ptr()
+    }
+
+    /// Returns `owned = ..` or `borrowed = ..` attribute if available.
+    pub fn pair(&self) -> Option<&PairAttr> {
+        self.attrs.pair()
+    }
+
+    /// Returns the span of `unsafe_pair` attribute if specified.
+    pub fn unsafe_pair(&self) -> Option<Span> {
+        self.attrs.unsafe_pair()
+    }
+
+    /// Returns `ops(..)` attribute.
+    pub fn ops(&self) -> &OpsAttr {
+        self.attrs.ops()
+    }
+
+    /// Returns `deref(..)` attribute if available.
+    pub fn deref(&self) -> Option<&DerefAttr> {
+        self.attrs.deref()
+    }
+
     /// Returns whether the base traits impls docs should be hidden.
     pub fn should_hide_base_impl_docs(&self) -> bool {
-        self.should_hide_base_impl_docs
+        self.attrs.hide_base_impl_docs()
+    }
+
+    /// Returns whether unchecked mutable access to the validated inner value is allowed.
+    pub fn should_allow_unchecked_mut(&self) -> bool {
+        self.attrs.allow_unchecked_mut()
+    }
+
+    /// Ensures unchecked mutable access to the inner value by `what` is allowed.
+    ///
+    /// Mutable access to the inner value can break the invariant checked by the validator, so
+    /// it requires `#[opaque_typedef(allow_unchecked_mut)]` for types with a validator.
+    pub fn check_unchecked_mut(&self, what: &str) -> syn::Result<()> {
+        match self.validator() {
+            Some(validator) if !self.should_allow_unchecked_mut() => Err(syn::Error::new(
+                validator.span(),
+                format!(
+                    "Validator and `{}` cannot be specified at the same time \
+                     without `#[opaque_typedef(allow_unchecked_mut)]`",
+                    what
+                ),
+            )),
+            _ => Ok(()),
+        }
     }
 
     /// Returns token stream of attributes for base trait impls.
@@ -127,19 +355,15 @@ impl<'a> Input<'a> {
         }
     }
 
-    /// Returns an iterator of attributes parsed as meta.
-    pub fn meta_attrs(&self) -> impl Iterator<Item = &Meta> {
-        self.meta_attrs.iter()
-    }
-
-    /// Ensures the type has acceptable `repr` meta for unsized type alias, or panic.
-    ///
-    /// # Panics
-    ///
-    /// Panics if an acceptable `repr` is not specified for the type.
-    pub fn ensure_acceptable_unsized_repr_or_panic(&self) {
-        if !self.meta_attrs().any(MetaExt::has_unsized_repr) {
-            panic!("`#[repr(C)]` or `#[repr(transparent)]` is required for unsized type alias");
+    /// Ensures the type has acceptable `repr` meta for unsized type alias.
+    pub fn ensure_acceptable_unsized_repr(&self) -> syn::Result<()> {
+        if self.repr_attrs.iter().any(MetaExt::has_unsized_repr) {
+            return Ok(());
+        }
+        let msg = "`#[repr(C)]` or `#[repr(transparent)]` is required for unsized type alias";
+        match self.repr_attrs.first() {
+            Some(repr) => Err(syn::Error::new_spanned(repr, msg)),
+            None => Err(syn::Error::new(self.ident().span(), msg)),
         }
     }
 }
@@ -150,12 +374,18 @@ pub struct FieldWrapper<'a> {
     index: usize,
     /// Field.
     field: &'a Field,
+    /// Field-level attributes.
+    attrs: FieldAttrs,
 }
 
 impl<'a> FieldWrapper<'a> {
     /// Creates a new accessor for the field at the given index.
-    fn new(index: usize, field: &'a Field) -> Self {
-        Self { index, field }
+    fn new(index: usize, field: &'a Field, attrs: FieldAttrs) -> Self {
+        Self {
+            index,
+            field,
+            attrs,
+        }
     }
 
     /// Creates a field.
@@ -175,6 +405,41 @@ impl<'a> FieldWrapper<'a> {
     pub fn ty(&self) -> &'a Type {
         &self.field.ty
     }
+
+    /// Returns `getter` attribute if specified.
+    pub fn getter(&self) -> Option<&GetterAttr> {
+        self.attrs.getter()
+    }
+
+    /// Returns the expression to initialize the field other than the inner field.
+    ///
+    /// `init = ..` is used if specified. Otherwise, `PhantomData` fields are initialized by
+    /// `PhantomData`, and other fields are initialized by `Default::default()`.
+    pub fn initializer(&self) -> TokenStream {
+        if let Some(init) = self.attrs.init() {
+            return quote!(#init);
+        }
+        if is_phantom_data(self.ty()) {
+            quote!(std::marker::PhantomData)
+        } else {
+            quote!(std::default::Default::default())
+        }
+    }
+}
+
+/// Returns true if the type seems to be `PhantomData<T>`.
+///
+/// The type is detected by its name, because the type resolution is not available in macros.
+fn is_phantom_data(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) if ty.qself.is_none() => matches!(
+            ty.path.segments.last(),
+            Some(segment) if segment.ident == "PhantomData"
+        ),
+        Type::Paren(ty) => is_phantom_data(&ty.elem),
+        Type::Group(ty) => is_phantom_data(&ty.elem),
+        _ => false,
+    }
 }
 
 /// Struct and tuple field accessor.
@@ -199,51 +464,320 @@ impl quote::ToTokens for FieldAccessor<'_> {
     }
 }
 
-/// Returns the primary field.
+/// Returns the fields of the struct.
+fn struct_fields(derive_input: &DeriveInput) -> syn::Result<&Fields> {
+    match &derive_input.data {
+        Data::Struct(v) => Ok(&v.fields),
+        Data::Enum(v) => Err(syn::Error::new_spanned(
+            v.enum_token,
+            "Only struct type is supported, but got an enum",
+        )),
+        Data::Union(v) => Err(syn::Error::new_spanned(
+            v.union_token,
+            "Only struct type is supported, but got a union",
+        )),
+    }
+}
+
+/// Returns the index of the primary field.
 ///
-/// # Panics
-/// Panics if no primary fields found or multiple fields are marked as primary.
-fn get_primary_field<'a>(
-    mut fields: impl Iterator<Item = &'a Field> + std::iter::ExactSizeIterator,
-) -> FieldWrapper<'a> {
-    let fields_len = fields.len();
-    if fields_len == 0 {
-        panic!("No fields found");
-    } else if fields_len == 1 {
-        return FieldWrapper::new(0, fields.next().unwrap());
+/// Returns an error if no primary fields found or multiple fields are marked as primary.
+fn get_primary_field_index(
+    ident: &Ident,
+    raw_fields: &Fields,
+    fields: &[FieldWrapper<'_>],
+) -> syn::Result<usize> {
+    match fields.len() {
+        0 => return Err(syn::Error::new(ident.span(), "No fields found")),
+        1 => return Ok(0),
+        _ => {}
     }
     let mut primary_fields = fields
-        .enumerate()
-        .filter(|&(_, field)| is_primary_field(field))
-        .map(|(i, field)| FieldWrapper::new(i, field));
+        .iter()
+        .filter_map(|field| field.attrs.inner().map(|span| (field.index(), span)));
     let first = match primary_fields.next() {
-        Some(v) => v,
-        None => panic!("There are multiple fields but none are marked as primary"),
+        Some((index, _)) => index,
+        None => {
+            return Err(syn::Error::new_spanned(
+                raw_fields,
+                "There are multiple fields but none are marked as primary \
+                 by `#[opaque_typedef(inner)]`",
+            ))
+        }
     };
-    if let Some(second) = primary_fields.next() {
-        panic!(
-            "Multiple fields are marked as primary: {}th and {}th",
-            first.index(),
-            second.index()
-        );
+    let mut errors = Errors::new();
+    for (index, span) in primary_fields {
+        errors.push(syn::Error::new(
+            span,
+            format!(
+                "Multiple fields are marked as primary: {}th and {}th",
+                first, index
+            ),
+        ));
     }
-    first
-}
+    errors.finish()?;
 
-/// Returns an iterator of the fields.
-fn fields(data: &Data) -> impl Iterator<Item = &Field> + std::iter::ExactSizeIterator {
-    let data = match &data {
-        Data::Struct(v) => v,
-        _ => panic!("Only struct type is supported"),
-    };
-    data.fields.iter()
+    Ok(first)
 }
 
-/// Checks if the given field is marked as primary.
-fn is_primary_field(field: &Field) -> bool {
-    field
-        .attrs
+/// Returns the indices of the fields marked by `#[opaque_typedef(inner)]`, for composite inputs.
+///
+/// Returns an error if the struct does not have named fields or no fields are marked.
+fn get_composite_field_indices(
+    ident: &Ident,
+    raw_fields: &Fields,
+    fields: &[FieldWrapper<'_>],
+) -> syn::Result<Vec<usize>> {
+    match raw_fields {
+        Fields::Named(_) => {}
+        Fields::Unnamed(_) => {
+            return Err(syn::Error::new_spanned(
+                raw_fields,
+                "Composite inner requires named fields",
+            ))
+        }
+        Fields::Unit => return Err(syn::Error::new(ident.span(), "No fields found")),
+    }
+    let indices = fields
         .iter()
-        .flat_map(|attr| attr.parse_meta())
-        .any(|meta| meta.has_level2_word("opaque_typedef", "inner"))
+        .filter(|field| field.attrs.inner().is_some())
+        .map(FieldWrapper::index)
+        .collect::<Vec<_>>();
+    if indices.is_empty() {
+        return Err(syn::Error::new_spanned(
+            raw_fields,
+            "No fields are marked as inner by `#[opaque_typedef(inner)]`",
+        ));
+    }
+    Ok(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enum_type() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            pub enum Simple<T> {
+                Inner(T),
+            }
+        };
+        assert!(Input::new(&input).is_err());
+    }
+
+    #[test]
+    fn no_fields() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            pub struct Simple;
+        };
+        assert!(Input::new(&input).is_err());
+    }
+
+    #[test]
+    fn multiple_fields_without_primary() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            pub struct Tagged<T, Tag> {
+                inner: T,
+                tag: Tag,
+            }
+        };
+        assert!(Input::new(&input).is_err());
+    }
+
+    #[test]
+    fn multiple_primary_fields() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            pub struct Tagged<T, Tag> {
+                #[opaque_typedef(inner)]
+                inner: T,
+                #[opaque_typedef(inner)]
+                tag: Tag,
+                #[opaque_typedef(inner)]
+                tag2: Tag,
+            }
+        };
+        let err = match Input::new(&input) {
+            Ok(_) => panic!("Multiple primary fields should be rejected"),
+            Err(e) => e,
+        };
+        assert_eq!(err.into_iter().count(), 2);
+    }
+
+    #[test]
+    fn multiple_errors() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            #[opaque_typedef(validate(validator = 42))]
+            #[opaque_typedef(validate(error = 42))]
+            pub struct Tagged<T, Tag> {
+                inner: T,
+                tag: Tag,
+            }
+        };
+        let err = match Input::new(&input) {
+            Ok(_) => panic!("Invalid input should be rejected"),
+            Err(e) => e,
+        };
+        assert_eq!(err.into_iter().count(), 3);
+    }
+
+    #[test]
+    fn construct() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            pub struct Tagged<T, Tag, U> {
+                #[opaque_typedef(inner)]
+                inner: T,
+                tag: std::marker::PhantomData<Tag>,
+                #[opaque_typedef(init = Marker::new())]
+                marker: Marker,
+                other: U,
+            }
+        };
+        let toks = Input::new(&input).unwrap().construct(quote!(value));
+        let expected = quote! {
+            Self {
+                inner: value,
+                tag: std::marker::PhantomData,
+                marker: Marker::new(),
+                other: std::default::Default::default(),
+            }
+        };
+        assert_eq!(toks.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn composite() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            pub struct Range<T> {
+                #[opaque_typedef(inner)]
+                start: T,
+                #[opaque_typedef(inner)]
+                end: T,
+            }
+        };
+        let input = Input::new_composite(&input).unwrap();
+        let indices = input
+            .primary_fields()
+            .map(FieldWrapper::index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [0, 1]);
+    }
+
+    #[test]
+    fn composite_invalid() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            pub struct Range<T>(#[opaque_typedef(inner)] T, #[opaque_typedef(inner)] T);
+        };
+        assert!(Input::new_composite(&input).is_err());
+
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            pub struct Range<T> {
+                start: T,
+                end: T,
+            }
+        };
+        assert!(Input::new_composite(&input).is_err());
+
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            pub struct Range<T> {
+                #[opaque_typedef(inner)]
+                start: T,
+                #[opaque_typedef(inner, init = T::default())]
+                end: T,
+            }
+        };
+        assert!(Input::new_composite(&input).is_err());
+    }
+
+    #[test]
+    fn getter_for_non_composite_inner() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            pub struct Range<T> {
+                #[opaque_typedef(inner)]
+                start: T,
+                #[opaque_typedef(getter)]
+                tag: PhantomData<T>,
+            }
+        };
+        assert!(Input::new_composite(&input).is_err());
+
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            pub struct Wrapper {
+                #[opaque_typedef(getter)]
+                value: i32,
+            }
+        };
+        assert!(Input::new(&input).is_err());
+    }
+
+    #[test]
+    fn composite_unsupported_keys() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefComposite)]
+            #[opaque_typedef(validate(error = Error, validator = validate))]
+            #[opaque_typedef(bound = "T: Ord", hide_base_impl_docs)]
+            pub struct Range<T> {
+                #[opaque_typedef(inner)]
+                start: T,
+                #[opaque_typedef(inner)]
+                end: T,
+            }
+        };
+        assert!(Input::new_composite(&input).is_ok());
+
+        let attrs = [
+            quote!(derive(Debug)),
+            quote!(ops(add)),
+            quote!(fmt(display)),
+            quote!(from_str),
+            quote!(as_ref(inner)),
+            quote!(as_mut(inner)),
+            quote!(deref(target = "T")),
+            quote!(cmp(ascii_case_insensitive)),
+            quote!(cmp_with(inner)),
+            quote!(convert(into_inner)),
+            quote!(smart_ptr(box)),
+            quote!(borrowed = "RangeRef"),
+            quote!(bound(Debug = "T: Debug")),
+            quote!(allow_unchecked_mut),
+        ];
+        for attr in &attrs {
+            let input = syn::parse_quote! {
+                #[derive(OpaqueTypedefComposite)]
+                #[opaque_typedef(#attr)]
+                pub struct Range<T> {
+                    #[opaque_typedef(inner)]
+                    start: T,
+                    #[opaque_typedef(inner)]
+                    end: T,
+                }
+            };
+            let err = match Input::new_composite(&input) {
+                Ok(_) => panic!("`{}` should be rejected", attr),
+                Err(e) => e.to_string(),
+            };
+            assert!(err.contains("not available for composite types"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn init_for_inner_field() {
+        let input = syn::parse_quote! {
+            #[derive(OpaqueTypedefSized)]
+            pub struct Wrapper(#[opaque_typedef(init = 0)] i32);
+        };
+        assert!(Input::new(&input).is_err());
+    }
 }
@@ -5,58 +5,70 @@
 
 extern crate proc_macro;
 
+use proc_macro2::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
 use crate::{
-    derive::base::{
-        gen_base_sized, gen_base_sized_infallible, gen_base_sized_mut, gen_base_unsized,
-        gen_base_unsized_infallible, gen_base_unsized_infallible_mut, gen_base_unsized_mut,
+    derive::{
+        base::{
+            gen_base_composite, gen_base_sized, gen_base_sized_infallible, gen_base_sized_mut,
+            gen_base_unsized, gen_base_unsized_infallible, gen_base_unsized_infallible_mut,
+            gen_base_unsized_mut,
+        },
+        gen_attr_impls, Kind,
     },
     input::Input,
 };
 
 pub(crate) mod attr;
 pub(crate) mod derive;
+pub(crate) mod error;
 pub(crate) mod input;
 
 /// The entrypoint for `#[derive(OpaqueTypedefSized)]`-ed types.
 #[proc_macro_derive(OpaqueTypedefSized, attributes(opaque_typedef))]
 pub fn opaque_typedef_sized(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse(input).unwrap();
-    match Input::new(&input) {
-        Ok(input) => gen_base_sized(&input).into(),
-        Err(e) => e.to_compile_error().into(),
-    }
+    let input = parse_macro_input!(input as DeriveInput);
+    derive(&input, |input| {
+        let mut toks = gen_base_sized(input);
+        toks.extend(gen_attr_impls(input, Kind::Sized)?);
+        Ok(toks)
+    })
 }
 
 /// The entrypoint for `#[derive(OpaqueTypedefSizedInfallible)]`-ed types.
 #[proc_macro_derive(OpaqueTypedefSizedInfallible, attributes(opaque_typedef))]
 pub fn opaque_typedef_sized_infallible(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse(input).unwrap();
-    match Input::new(&input) {
-        Ok(input) => gen_base_sized_infallible(&input)
-            .unwrap_or_else(|e| e.to_compile_error())
-            .into(),
-        Err(e) => e.to_compile_error().into(),
-    }
+    let input = parse_macro_input!(input as DeriveInput);
+    derive(&input, gen_base_sized_infallible)
 }
 
 /// The entrypoint for `#[derive(OpaqueTypedefSizedMut)]`-ed types.
 #[proc_macro_derive(OpaqueTypedefSizedMut, attributes(opaque_typedef))]
 pub fn opaque_typedef_sized_mut(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse(input).unwrap();
-    match Input::new(&input) {
-        Ok(input) => gen_base_sized_mut(&input).into(),
-        Err(e) => e.to_compile_error().into(),
-    }
+    let input = parse_macro_input!(input as DeriveInput);
+    derive(&input, gen_base_sized_mut)
+}
+
+/// The entrypoint for `#[derive(OpaqueTypedefComposite)]`-ed types.
+#[proc_macro_derive(OpaqueTypedefComposite, attributes(opaque_typedef))]
+pub fn opaque_typedef_composite(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    Input::new_composite(&input)
+        .map(|input| gen_base_composite(&input))
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
 }
 
 /// The entrypoint for `#[derive(OpaqueTypedefUnsized)]`-ed types.
 #[proc_macro_derive(OpaqueTypedefUnsized, attributes(opaque_typedef))]
 pub fn opaque_typedef_unsized(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse(input).unwrap();
-    match Input::new(&input) {
-        Ok(input) => gen_base_unsized(&input).into(),
-        Err(e) => e.to_compile_error().into(),
-    }
+    let input = parse_macro_input!(input as DeriveInput);
+    derive(&input, |input| {
+        let mut toks = gen_base_unsized(input)?;
+        toks.extend(gen_attr_impls(input, Kind::Unsized)?);
+        Ok(toks)
+    })
 }
 
 /// The entrypoint for `#[derive(OpaqueTypedefUnsizedInfallible)]`-ed types.
@@ -64,23 +76,15 @@ pub fn opaque_typedef_unsized(input: proc_macro::TokenStream) -> proc_macro::Tok
 pub fn opaque_typedef_unsized_infallible(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let input = syn::parse(input).unwrap();
-    match Input::new(&input) {
-        Ok(input) => gen_base_unsized_infallible(&input)
-            .unwrap_or_else(|e| e.to_compile_error())
-            .into(),
-        Err(e) => e.to_compile_error().into(),
-    }
+    let input = parse_macro_input!(input as DeriveInput);
+    derive(&input, gen_base_unsized_infallible)
 }
 
 /// The entrypoint for `#[derive(OpaqueTypedefUnsizedMut)]`-ed types.
 #[proc_macro_derive(OpaqueTypedefUnsizedMut, attributes(opaque_typedef))]
 pub fn opaque_typedef_unsized_mut(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse(input).unwrap();
-    match Input::new(&input) {
-        Ok(input) => gen_base_unsized_mut(&input).into(),
-        Err(e) => e.to_compile_error().into(),
-    }
+    let input = parse_macro_input!(input as DeriveInput);
+    derive(&input, gen_base_unsized_mut)
 }
 
 /// The entrypoint for `#[derive(OpaqueTypedefUnsizedInfallibleMut)]`-ed types.
@@ -88,11 +92,17 @@ pub fn opaque_typedef_unsized_mut(input: proc_macro::TokenStream) -> proc_macro:
 pub fn opaque_typedef_unsized_infallible_mut(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let input = syn::parse(input).unwrap();
-    match Input::new(&input) {
-        Ok(input) => gen_base_unsized_infallible_mut(&input)
-            .unwrap_or_else(|e| e.to_compile_error())
-            .into(),
-        Err(e) => e.to_compile_error().into(),
-    }
+    let input = parse_macro_input!(input as DeriveInput);
+    derive(&input, gen_base_unsized_infallible_mut)
+}
+
+/// Runs the given codegen for the derive input, and reports errors as compile errors.
+fn derive(
+    input: &DeriveInput,
+    gen: impl FnOnce(&Input) -> syn::Result<TokenStream>,
+) -> proc_macro::TokenStream {
+    Input::new(input)
+        .and_then(|input| gen(&input))
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
 }
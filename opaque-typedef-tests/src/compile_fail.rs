@@ -0,0 +1,111 @@
+//! Codes which should be rejected at compile time.
+//!
+//! # Owned and borrowed types with different validation
+//!
+//! Owned and borrowed types can be linked by `owned = ..` or `borrowed = ..` only when both
+//! types have infallible conversions.
+//!
+//! ```
+//! use opaque_typedef::{OpaqueTypedefSized, OpaqueTypedefUnsized};
+//!
+//! #[derive(OpaqueTypedefUnsized)]
+//! #[repr(transparent)]
+//! pub struct MyStr(str);
+//!
+//! #[derive(OpaqueTypedefSized)]
+//! #[opaque_typedef(borrowed = "MyStr")]
+//! pub struct MyString(String);
+//! ```
+//!
+//! ```compile_fail,E0271
+//! use opaque_typedef::{OpaqueTypedefSized, OpaqueTypedefUnsized};
+//!
+//! fn validate(s: &str) -> Result<&str, ()> {
+//!     if s.is_ascii() {
+//!         Ok(s)
+//!     } else {
+//!         Err(())
+//!     }
+//! }
+//!
+//! #[derive(OpaqueTypedefUnsized)]
+//! #[repr(transparent)]
+//! #[opaque_typedef(validate(error = "()", validator = "validate"))]
+//! pub struct AsciiStr(str);
+//!
+//! // The owned type without validation would create invalid `&AsciiStr`.
+//! #[derive(OpaqueTypedefSized)]
+//! #[opaque_typedef(borrowed = "AsciiStr")]
+//! pub struct Unchecked(String);
+//! ```
+//!
+//! # Sized inner fields of unsized types
+//!
+//! The inner field of unsized types should be unsized.
+//! Otherwise, the derive fails to compile for non-generic types, and the conversions from the
+//! inner type fail to compile for generic types.
+//!
+//! ```compile_fail,E0080
+//! use opaque_typedef::OpaqueTypedefUnsized;
+//!
+//! #[derive(OpaqueTypedefUnsized)]
+//! #[repr(transparent)]
+//! pub struct Foo(u32);
+//! ```
+//!
+//! # Non-zero-sized extra fields of unsized types
+//!
+//! Fields other than the inner field of unsized types should be zero-sized and have alignment 1.
+//! Otherwise, the derive fails to compile for non-generic types, and the conversions from the
+//! inner type fail to compile for generic types.
+//!
+//! ```
+//! use std::marker::PhantomData;
+//!
+//! use opaque_typedef::OpaqueTypedefUnsized;
+//!
+//! #[derive(OpaqueTypedefUnsized)]
+//! #[repr(C)]
+//! pub struct TaggedStr<Tag> {
+//!     tag: PhantomData<Tag>,
+//!     #[opaque_typedef(inner)]
+//!     inner: str,
+//! }
+//!
+//! let _ = TaggedStr::<()>::try_from_inner("hello");
+//! ```
+//!
+//! ```compile_fail,E0080
+//! use opaque_typedef::OpaqueTypedefUnsized;
+//!
+//! #[derive(OpaqueTypedefUnsized)]
+//! #[repr(C)]
+//! pub struct TaggedStr {
+//!     tag: u32,
+//!     #[opaque_typedef(inner)]
+//!     inner: str,
+//! }
+//! ```
+//!
+//! # Comparison by key with comparison of raw inner values
+//!
+//! `cmp(..)` cannot be specified with `cmp_with(..)`, `owned = ..`, or `borrowed = ..`, which
+//! compare the inner values without the key.
+//!
+//! ```
+//! use opaque_typedef::OpaqueTypedefUnsized;
+//!
+//! #[derive(OpaqueTypedefUnsized)]
+//! #[repr(transparent)]
+//! #[opaque_typedef(cmp(ascii_case_insensitive))]
+//! pub struct HeaderName(str);
+//! ```
+//!
+//! ```compile_fail
+//! use opaque_typedef::OpaqueTypedefUnsized;
+//!
+//! #[derive(OpaqueTypedefUnsized)]
+//! #[repr(transparent)]
+//! #[opaque_typedef(cmp(ascii_case_insensitive), cmp_with(inner))]
+//! pub struct HeaderName(str);
+//! ```
@@ -1 +1,3 @@
 //! Tests and examples for `opaque-typedef` crate.
+
+pub mod compile_fail;
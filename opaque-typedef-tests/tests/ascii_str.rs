@@ -1,7 +1,13 @@
 //! Ascii string.
 
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::rc::Rc;
+use std::sync::Arc;
+
 use opaque_typedef::{
-    OpaqueTypedefSized, OpaqueTypedefSizedMut, OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut,
+    cast, OpaqueTypedefSized, OpaqueTypedefSizedMut, OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -50,14 +56,22 @@ fn validate_string(s: String) -> Result<String, FromAsciiError> {
 }
 
 /// Ascii string slice.
-#[derive(Debug, OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut)]
+#[derive(Debug, PartialEq, Eq, Hash, OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut)]
 #[repr(transparent)]
+#[opaque_typedef(allow_unchecked_mut)]
 #[opaque_typedef(validate(error = "AsciiError", validator = "validate_str"))]
+#[opaque_typedef(as_ref(inner, self, "[u8]"), deref(target = "inner"))]
+#[opaque_typedef(cmp_with(inner, "&str", String, "Cow<'_, str>"))]
+#[opaque_typedef(convert(from_inner, into_inner), smart_ptr(box, rc, arc))]
 pub struct AsciiStr(str);
 
 /// Ascii owned string.
-#[derive(Debug, Clone, OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, OpaqueTypedefSized, OpaqueTypedefSizedMut)]
+#[opaque_typedef(allow_unchecked_mut)]
 #[opaque_typedef(validate(error = "FromAsciiError", validator = "validate_string"))]
+#[opaque_typedef(as_ref(inner, str, "[u8]"), borrowed = "AsciiStr")]
+#[opaque_typedef(unsafe_pair)]
+#[opaque_typedef(cmp_with(base = str, inner, str, "&str"))]
 pub struct AsciiString(String);
 
 #[cfg(test)]
@@ -78,6 +92,16 @@ mod ascii_str {
         assert_eq!(my.as_inner(), inner);
     }
 
+    #[test]
+    fn convert() {
+        let my = <&AsciiStr>::try_from("hello").unwrap();
+        assert_eq!(<&str>::from(my), "hello");
+        let inner: &str = my.into();
+        assert_eq!(inner, "hello");
+        let my: Result<&AsciiStr, _> = "\u{3042}".try_into();
+        assert_eq!(my.unwrap_err().valid_up_to(), 0);
+    }
+
     #[test]
     fn from_inner_unchecked() {
         let inner = "hello";
@@ -97,21 +121,117 @@ mod ascii_str {
         let mut inner: String = "hello".to_owned();
         let inner_readonly = "hello";
         let my: &mut AsciiStr = AsciiStr::try_from_inner_mut(&mut inner).unwrap();
-        assert_eq!(my.as_inner_mut(), inner_readonly);
+        assert_eq!(my.as_inner(), inner_readonly);
     }
 
     #[test]
     fn try_from_inner_mut_fail() {
-        let inner = "hello\u{FFFD}";
-        let err = AsciiStr::try_from_inner(inner).unwrap_err();
+        let mut inner = "hello\u{FFFD}".to_owned();
+        let err = AsciiStr::try_from_inner_mut(&mut inner).unwrap_err();
         assert_eq!(err.valid_up_to(), 5);
     }
 
     #[test]
-    fn as_inner_mut() {
+    fn as_inner_mut_unchecked() {
         let mut inner = "hello".to_owned();
         let my = AsciiStr::try_from_inner_mut(&mut inner).unwrap();
-        let _: &mut str = my.as_inner_mut();
+        let inner_mut: &mut str = unsafe { my.as_inner_mut_unchecked() };
+        inner_mut.make_ascii_uppercase();
+        assert_eq!(my.as_inner(), "HELLO");
+    }
+
+    #[test]
+    fn as_ref() {
+        let my = AsciiStr::try_from_inner("hello").unwrap();
+        assert_eq!(AsRef::<str>::as_ref(my), "hello");
+        assert_eq!(AsRef::<[u8]>::as_ref(my), b"hello");
+        fn as_ascii_str<T: AsRef<AsciiStr> + ?Sized>(v: &T) -> &AsciiStr {
+            v.as_ref()
+        }
+        assert_eq!(as_ascii_str(my).as_inner(), "hello");
+    }
+
+    #[test]
+    fn cmp_with() {
+        let my = AsciiStr::try_from_inner("hello").unwrap();
+        let (hello, abc) = ("hello".to_owned(), "abc".to_owned());
+        assert!(*my == *"hello");
+        assert!(*"hello" == *my);
+        assert!(*my == "hello");
+        assert!("hello" == *my);
+        assert!(my == hello);
+        assert!(hello == my);
+        assert!(*my == Cow::Borrowed("hello"));
+        assert!(Cow::<str>::Owned("hello".to_owned()) == my);
+        assert!(*my < *"world");
+        assert!(my > abc);
+        assert!(*my != "world");
+    }
+
+    #[test]
+    fn deref() {
+        let my = AsciiStr::try_from_inner("hello").unwrap();
+        assert_eq!(my.len(), 5);
+        assert!(my.starts_with("he"));
+    }
+
+    #[test]
+    fn try_box_from_inner() {
+        let inner: Box<str> = "hello".into();
+        let my: Box<AsciiStr> = cast::try_box_from_inner(inner).unwrap();
+        assert_eq!(my.as_inner(), "hello");
+        let inner: Box<str> = cast::box_into_inner(my);
+        assert_eq!(&*inner, "hello");
+    }
+
+    #[test]
+    fn try_box_from_inner_fail() {
+        let inner: Box<str> = "hello\u{FFFD}".into();
+        let (inner, err) = cast::try_box_from_inner::<AsciiStr>(inner).unwrap_err();
+        assert_eq!(&*inner, "hello\u{FFFD}");
+        assert_eq!(err.valid_up_to(), 5);
+    }
+
+    #[test]
+    fn try_rc_from_inner() {
+        let inner: Rc<str> = "hello".into();
+        let my: Rc<AsciiStr> = cast::try_rc_from_inner(inner.clone()).unwrap();
+        assert_eq!(Rc::strong_count(&inner), 2);
+        assert_eq!(&*cast::rc_into_inner(my), "hello");
+    }
+
+    #[test]
+    fn try_from_inner_smart_ptr() {
+        let my = AsciiStr::try_from_inner_box("hello".into()).unwrap();
+        assert_eq!(&*my.into_inner_box(), "hello");
+        let inner: Rc<str> = "hello".into();
+        let my = AsciiStr::try_from_inner_rc(inner.clone()).unwrap();
+        assert_eq!(Rc::strong_count(&inner), 2);
+        assert_eq!(&*my.into_inner_rc(), "hello");
+        let (inner, err) = AsciiStr::try_from_inner_arc("hello\u{FFFD}".into()).unwrap_err();
+        assert_eq!(&*inner, "hello\u{FFFD}");
+        assert_eq!(err.valid_up_to(), 5);
+    }
+
+    #[test]
+    fn smart_ptr_from_ref() {
+        let my = AsciiStr::try_from_inner("hello").unwrap();
+        let boxed: Box<AsciiStr> = my.into();
+        let cloned = boxed.clone();
+        assert_eq!(cloned, boxed);
+        assert_eq!(&*cloned, my);
+        let rc: Rc<AsciiStr> = my.into();
+        assert_eq!(&*rc, my);
+        let arc = Arc::<AsciiStr>::from(my);
+        assert_eq!(arc.as_inner(), "hello");
+    }
+
+    #[test]
+    fn try_arc_from_inner_fail() {
+        let inner: Arc<str> = "hello\u{FFFD}".into();
+        let (inner, err) = cast::try_arc_from_inner::<AsciiStr>(inner).unwrap_err();
+        assert_eq!(&*inner, "hello\u{FFFD}");
+        assert_eq!(err.valid_up_to(), 5);
     }
 }
 
@@ -149,9 +269,114 @@ mod ascii_string {
     }
 
     #[test]
-    fn as_inner_mut() {
+    fn as_inner_mut_unchecked() {
         let inner = "hello".to_owned();
         let mut my = AsciiString::try_from_inner(inner).unwrap();
-        let _: &mut String = my.as_inner_mut();
+        let inner_mut: &mut String = unsafe { my.as_inner_mut_unchecked() };
+        inner_mut.push('!');
+        assert_eq!(my.as_inner(), "hello!");
+    }
+
+    #[test]
+    fn as_ref() {
+        let my = AsciiString::try_from_inner("hello".to_owned()).unwrap();
+        assert_eq!(AsRef::<String>::as_ref(&my), "hello");
+        assert_eq!(AsRef::<str>::as_ref(&my), "hello");
+        assert_eq!(AsRef::<[u8]>::as_ref(&my), b"hello");
+    }
+
+    #[test]
+    fn cmp_with() {
+        let my = AsciiString::try_from_inner("hello".to_owned()).unwrap();
+        let my_str = AsciiStr::try_from_inner("hello").unwrap();
+        let hello = "hello".to_owned();
+        assert!(my == hello);
+        assert!(hello == my);
+        assert!(my == *"hello");
+        assert!(my == "hello");
+        assert!("hello" == my);
+        assert!(my == *my_str);
+        assert!(*my_str == my);
+        assert!(my == my_str);
+        assert!(my_str == my);
+        assert!(my < "world");
+        assert!(my_str < AsciiString::try_from_inner("world".to_owned()).unwrap());
+    }
+
+    #[test]
+    fn deref() {
+        let my = AsciiString::try_from_inner("hello".to_owned()).unwrap();
+        let s: &str = &my;
+        assert_eq!(s, "hello");
+        assert_eq!(my.len(), 5);
+    }
+
+    #[test]
+    fn borrowed() {
+        let my = AsciiString::try_from_inner("hello".to_owned()).unwrap();
+        let my_str = AsciiStr::try_from_inner("hello").unwrap();
+        assert_eq!(&*my, my_str);
+        assert_eq!(Borrow::<AsciiStr>::borrow(&my), my_str);
+        assert_eq!(AsRef::<AsciiStr>::as_ref(&my), my_str);
+        assert_eq!(my_str.to_owned(), my);
+    }
+
+    #[test]
+    fn hash_map_get_borrowed() {
+        let mut map = HashMap::new();
+        map.insert(AsciiString::try_from_inner("hello".to_owned()).unwrap(), 42);
+        let key = AsciiStr::try_from_inner("hello").unwrap();
+        assert_eq!(map.get(key), Some(&42));
+    }
+
+    #[test]
+    fn cow() {
+        let my_str = AsciiStr::try_from_inner("hello").unwrap();
+        let mut cow = Cow::Borrowed(my_str);
+        assert!(matches!(cow, Cow::Borrowed(_)));
+        cow.to_mut()
+            .try_modify_inner(|s| s.push_str(", world"))
+            .unwrap();
+        assert!(matches!(cow, Cow::Owned(_)));
+        assert!(*cow == "hello, world");
+    }
+
+    #[test]
+    fn try_modify_inner() {
+        let mut my = AsciiString::try_from_inner("hello".to_owned()).unwrap();
+        let len = my
+            .try_modify_inner(|s| {
+                s.push_str(", world");
+                s.len()
+            })
+            .unwrap();
+        assert_eq!(len, 12);
+        assert_eq!(my.as_inner(), "hello, world");
+    }
+
+    #[test]
+    fn try_modify_inner_fail() {
+        let mut my = AsciiString::try_from_inner("hello".to_owned()).unwrap();
+        let err = my.try_modify_inner(|s| s.push('\u{FFFD}')).unwrap_err();
+        assert_eq!(err.ascii_error().valid_up_to(), 5);
+        assert_eq!(my.as_inner(), "hello");
+    }
+
+    #[test]
+    fn inner_mut_guard_commit() {
+        let mut my = AsciiString::try_from_inner("hello".to_owned()).unwrap();
+        let mut guard = my.inner_mut_guard();
+        guard.push('!');
+        guard.commit().unwrap();
+        assert_eq!(my.as_inner(), "hello!");
+    }
+
+    #[test]
+    fn inner_mut_guard_drop() {
+        let mut my = AsciiString::try_from_inner("hello".to_owned()).unwrap();
+        let mut guard = my.inner_mut_guard();
+        guard.push('!');
+        drop(guard);
+        assert_eq!(my.as_inner(), "hello");
     }
 }
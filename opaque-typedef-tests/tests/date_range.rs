@@ -0,0 +1,81 @@
+//! Range with the invariant spanning multiple fields.
+
+use std::marker::PhantomData;
+
+use opaque_typedef::OpaqueTypedefComposite;
+
+/// An error indicating the start is after the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reversed;
+
+fn validate_range<T: PartialOrd>((start, end): (T, T)) -> Result<(T, T), Reversed> {
+    if start <= end {
+        Ok((start, end))
+    } else {
+        Err(Reversed)
+    }
+}
+
+/// Range of days, where `start <= end` holds.
+#[derive(Debug, OpaqueTypedefComposite)]
+#[opaque_typedef(validate(error = Reversed, validator = validate_range))]
+#[opaque_typedef(bound = "T: PartialOrd")]
+pub struct DateRange<T> {
+    /// Start day.
+    #[opaque_typedef(inner, getter(vis = "pub"))]
+    start: T,
+    /// End day.
+    #[opaque_typedef(inner, getter(vis = "pub"))]
+    end: T,
+    /// Unit.
+    unit: PhantomData<fn() -> T>,
+}
+
+/// Labeled value, without validation.
+#[derive(Debug, OpaqueTypedefComposite)]
+pub struct Labeled {
+    #[opaque_typedef(inner, getter(name = "label_ref"))]
+    label: String,
+    #[opaque_typedef(inner, getter)]
+    value: i32,
+}
+
+impl Labeled {
+    /// Returns the label, which would clash with the getter without renaming.
+    pub fn label(&self) -> &str {
+        self.label_ref()
+    }
+}
+
+#[test]
+fn try_from_inner() {
+    let range = DateRange::try_from_inner((1, 3)).unwrap();
+    assert_eq!(range.start(), &1);
+    assert_eq!(range.end(), &3);
+    assert!(DateRange::try_from_inner((2, 2)).is_ok());
+    assert_eq!(DateRange::try_from_inner((3, 1)).err(), Some(Reversed));
+}
+
+#[test]
+fn into_inner() {
+    let range = DateRange::try_from_inner((1u8, 3)).unwrap();
+    let (start, end) = range.into_inner();
+    assert_eq!((start, end), (1, 3));
+}
+
+#[test]
+fn from_inner_unchecked() {
+    let range = unsafe { DateRange::from_inner_unchecked((3, 1)) };
+    assert_eq!((range.start(), range.end()), (&3, &1));
+}
+
+#[test]
+fn no_validation() {
+    let labeled = match Labeled::try_from_inner(("answer".to_owned(), 42)) {
+        Ok(v) => v,
+        Err(e) => match e {},
+    };
+    assert_eq!(labeled.label(), "answer");
+    assert_eq!(*labeled.value(), 42);
+    assert_eq!(labeled.into_inner(), ("answer".to_owned(), 42));
+}
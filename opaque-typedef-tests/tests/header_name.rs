@@ -0,0 +1,94 @@
+//! Strings compared by keys.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+use opaque_typedef::{
+    OpaqueTypedefSized, OpaqueTypedefSizedInfallible, OpaqueTypedefUnsized,
+    OpaqueTypedefUnsizedInfallible,
+};
+
+/// Header name, compared in ASCII case-insensitive manner.
+#[derive(Debug, OpaqueTypedefUnsized, OpaqueTypedefUnsizedInfallible)]
+#[repr(transparent)]
+#[opaque_typedef(cmp(ascii_case_insensitive))]
+pub struct HeaderName(str);
+
+/// Owned string, compared without leading and trailing whitespaces.
+#[derive(Debug, OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+#[opaque_typedef(cmp(key = str::trim))]
+pub struct Trimmed(String);
+
+fn hash_of<T: ?Sized + Hash>(v: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    v.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn ascii_case_insensitive() {
+    let lower = HeaderName::from_inner("content-type");
+    let mixed = HeaderName::from_inner("Content-Type");
+    assert_eq!(lower, mixed);
+    assert_eq!(hash_of(lower), hash_of(mixed));
+    assert_ne!(lower, HeaderName::from_inner("content-length"));
+    assert!(HeaderName::from_inner("Accept") < HeaderName::from_inner("content-type"));
+    assert!(HeaderName::from_inner("content") < HeaderName::from_inner("CONTENT-TYPE"));
+}
+
+#[test]
+fn ascii_case_insensitive_map() {
+    let mut map = HashMap::new();
+    map.insert(HeaderName::from_inner("Content-Type"), "text/plain");
+    assert_eq!(
+        map.get(HeaderName::from_inner("CONTENT-TYPE")),
+        Some(&"text/plain")
+    );
+
+    let set = ["b", "A", "a", "c", "B"]
+        .iter()
+        .map(|s| HeaderName::from_inner(*s))
+        .collect::<BTreeSet<_>>();
+    let names = set
+        .iter()
+        .map(|s| s.as_inner().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b", "c"]);
+}
+
+#[test]
+fn ascii_case_insensitive_consistent() {
+    let names = [
+        "content-type",
+        "Content-Type",
+        "CONTENT-TYPE",
+        "content-length",
+        "Accept",
+    ]
+    .iter()
+    .map(|s| HeaderName::from_inner(*s))
+    .collect::<Vec<_>>();
+    for a in &names {
+        for b in &names {
+            assert_eq!(a == b, a.cmp(b) == Ordering::Equal);
+            assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+            if a == b {
+                assert_eq!(hash_of(*a), hash_of(*b));
+            }
+        }
+    }
+}
+
+#[test]
+fn key_fn() {
+    let foo = Trimmed::from_inner(" foo ".to_owned());
+    assert_eq!(foo, Trimmed::from_inner("foo".to_owned()));
+    assert_eq!(
+        hash_of(&foo),
+        hash_of(&Trimmed::from_inner("foo\n".to_owned()))
+    );
+    assert!(foo > Trimmed::from_inner("bar ".to_owned()));
+    assert!(Trimmed::from_inner("  z".to_owned()) > foo);
+}
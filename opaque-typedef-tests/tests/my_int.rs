@@ -0,0 +1,322 @@
+//! Integer newtypes.
+
+// Operators for references are tested explicitly.
+#![allow(clippy::op_ref)]
+
+use std::convert::{TryFrom, TryInto};
+
+use opaque_typedef::{FromStrError, OpaqueTypedefSized, OpaqueTypedefSizedInfallible};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OddError;
+
+fn validate_even(v: i32) -> Result<i32, OddError> {
+    if v % 2 == 0 {
+        Ok(v)
+    } else {
+        Err(OddError)
+    }
+}
+
+/// My integer.
+#[derive(Clone, Copy, OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+#[opaque_typedef(fmt(display, lower_hex, upper_hex, octal, binary, lower_exp, upper_exp))]
+#[opaque_typedef(fmt(debug = "named"), convert(from_inner, into_inner))]
+#[opaque_typedef(ops(add, sub, mul, div, rem, neg))]
+#[opaque_typedef(ops(add_assign, sub_assign, mul_assign, div_assign, rem_assign))]
+pub struct MyInt(i32);
+
+/// Even integer, whose operators panic on odd results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, OpaqueTypedefSized)]
+#[opaque_typedef(validate(error = OddError, validator = validate_even))]
+#[opaque_typedef(ops(add, div, neg, add_assign, div_assign))]
+#[opaque_typedef(convert(from_inner, into_inner))]
+pub struct EvenInt(i32);
+
+/// Even integer, whose operators return `Result`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, OpaqueTypedefSized)]
+#[opaque_typedef(validate(error = OddError, validator = validate_even))]
+#[opaque_typedef(ops(add, div, neg, on_invalid = "result"))]
+pub struct CheckedEvenInt(i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError;
+
+fn validate_nibble(v: u8) -> Result<u8, OverflowError> {
+    if v <= 0xf {
+        Ok(v)
+    } else {
+        Err(OverflowError)
+    }
+}
+
+/// Permission flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+#[opaque_typedef(ops(
+    bit_and,
+    bit_or,
+    bit_xor,
+    not,
+    shl(rhs = "u32"),
+    shr(rhs = "u32"),
+    mask
+))]
+#[opaque_typedef(ops(bit_and_assign, bit_or_assign, bit_xor_assign))]
+#[opaque_typedef(ops(shl_assign(rhs = "u32"), shr_assign(rhs = "u32")))]
+pub struct Permissions(u8);
+
+/// 4-bit flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, OpaqueTypedefSized)]
+#[opaque_typedef(validate(error = OverflowError, validator = validate_nibble))]
+#[opaque_typedef(ops(bit_or, shl(rhs = "u32"), shl_assign(rhs = "u32"), mask))]
+pub struct Nibble(u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyError;
+
+fn validate_nonempty(v: u8) -> Result<u8, EmptyError> {
+    if v != 0 {
+        Ok(v)
+    } else {
+        Err(EmptyError)
+    }
+}
+
+/// Non-empty flags, whose mask methods return `Result`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, OpaqueTypedefSized)]
+#[opaque_typedef(validate(error = EmptyError, validator = validate_nonempty))]
+#[opaque_typedef(ops(mask, on_invalid = "result"))]
+pub struct NonEmptyFlags(u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrivilegedPortError;
+
+impl std::fmt::Display for PrivilegedPortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("privileged port")
+    }
+}
+
+impl std::error::Error for PrivilegedPortError {}
+
+fn validate_port(v: u16) -> Result<u16, PrivilegedPortError> {
+    if v >= 1024 {
+        Ok(v)
+    } else {
+        Err(PrivilegedPortError)
+    }
+}
+
+/// Counter, without `OpaqueTypedefSizedInfallible`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, OpaqueTypedefSized)]
+#[opaque_typedef(convert(from_inner))]
+pub struct Count(u32);
+
+/// Unprivileged port number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, OpaqueTypedefSized)]
+#[opaque_typedef(validate(error = PrivilegedPortError, validator = validate_port))]
+#[opaque_typedef(from_str)]
+pub struct Port(u16);
+
+#[test]
+fn display() {
+    let v = MyInt::from_inner(42);
+    assert_eq!(format!("{}", v), "42");
+    assert_eq!(format!("{:>5}|{:<4}|{:+}", v, v, v), "   42|42  |+42");
+}
+
+#[test]
+fn radix() {
+    let v = MyInt::from_inner(42);
+    assert_eq!(
+        format!("{:x} {:X} {:o} {:b}", v, v, v, v),
+        "2a 2A 52 101010"
+    );
+    assert_eq!(format!("{:#06x}", v), "0x002a");
+}
+
+#[test]
+fn exp() {
+    let v = MyInt::from_inner(1200);
+    assert_eq!(format!("{:e} {:E}", v, v), "1.2e3 1.2E3");
+}
+
+#[test]
+fn debug_named() {
+    let v = MyInt::from_inner(42);
+    assert_eq!(format!("{:?}", v), "MyInt(42)");
+    assert_eq!(format!("{:#?}", v), "MyInt(\n    42,\n)");
+}
+
+#[test]
+fn ops_infallible() {
+    let (a, b) = (MyInt::from_inner(7), MyInt::from_inner(2));
+    assert_eq!((a + b).into_inner(), 9);
+    assert_eq!((&a - b).into_inner(), 5);
+    assert_eq!((a * &b).into_inner(), 14);
+    assert_eq!((&a / &b).into_inner(), 3);
+    assert_eq!((a % 4).into_inner(), 3);
+    assert_eq!((&a + 1).into_inner(), 8);
+    assert_eq!((-a).into_inner(), -7);
+    assert_eq!((-&b).into_inner(), -2);
+}
+
+#[test]
+fn ops_assign_infallible() {
+    let mut v = MyInt::from_inner(7);
+    v += MyInt::from_inner(3);
+    v -= &MyInt::from_inner(1);
+    v *= 2;
+    v /= 3;
+    v %= 4;
+    assert_eq!(v.into_inner(), 2);
+}
+
+#[test]
+fn ops_validated() {
+    let (a, b) = (
+        EvenInt::try_from_inner(8).unwrap(),
+        EvenInt::try_from_inner(2).unwrap(),
+    );
+    assert_eq!((a + b).into_inner(), 10);
+    assert_eq!((&a / &b).into_inner(), 4);
+    assert_eq!((-a).into_inner(), -8);
+    let mut v = a;
+    v += 4;
+    v /= &b;
+    assert_eq!(v.into_inner(), 6);
+}
+
+#[test]
+#[should_panic(expected = "Invalid result of `Div`")]
+fn ops_validated_panic() {
+    let _ = EvenInt::try_from_inner(6).unwrap() / 2;
+}
+
+#[test]
+#[should_panic(expected = "Invalid result of `AddAssign`")]
+fn ops_assign_validated_panic() {
+    let mut v = EvenInt::try_from_inner(6).unwrap();
+    v += 1;
+}
+
+#[test]
+fn ops_result() {
+    let (a, b) = (
+        CheckedEvenInt::try_from_inner(8).unwrap(),
+        CheckedEvenInt::try_from_inner(2).unwrap(),
+    );
+    assert_eq!((a + b).map(CheckedEvenInt::into_inner), Ok(10));
+    assert_eq!((&a / 4).map(CheckedEvenInt::into_inner), Ok(2));
+    assert_eq!(a / 8, Err(OddError));
+    assert_eq!((-&b).map(CheckedEvenInt::into_inner), Ok(-2));
+}
+
+#[test]
+fn bit_ops_infallible() {
+    let (r, w) = (
+        Permissions::from_inner(0b100),
+        Permissions::from_inner(0b010),
+    );
+    assert_eq!((r | w).into_inner(), 0b110);
+    assert_eq!((&r & &w).into_inner(), 0);
+    assert_eq!((r ^ 0b110).into_inner(), 0b010);
+    assert_eq!((!r).into_inner(), 0b1111_1011);
+    assert_eq!((&w << 1).into_inner(), 0b100);
+    assert_eq!((r >> 2).into_inner(), 0b001);
+
+    let mut v = r;
+    v |= w;
+    v &= &Permissions::from_inner(0b011);
+    v ^= 0b001;
+    v <<= 2;
+    v >>= 1;
+    assert_eq!(v.into_inner(), 0b110);
+}
+
+#[test]
+fn mask_infallible() {
+    let (r, w) = (
+        Permissions::from_inner(0b100),
+        Permissions::from_inner(0b010),
+    );
+    let mut v = Permissions::from_inner(0);
+    v.insert(&r);
+    v.insert(&w);
+    assert!(v.contains(&r));
+    assert!(v.contains(&(r | w)));
+    v.remove(&r);
+    assert!(!v.contains(&r));
+    assert!(v.contains(&w));
+}
+
+#[test]
+fn bit_ops_validated() {
+    let v = Nibble::try_from_inner(0b0011).unwrap();
+    assert_eq!((v << 2).into_inner(), 0b1100);
+    let mut m = v;
+    m.insert(&Nibble::try_from_inner(0b1000).unwrap());
+    assert_eq!(m.into_inner(), 0b1011);
+}
+
+#[test]
+fn mask_result() {
+    let mut m = NonEmptyFlags::try_from_inner(0b0001).unwrap();
+    let flag = NonEmptyFlags::try_from_inner(0b1000).unwrap();
+    assert_eq!(m.insert(&flag), Ok(()));
+    assert_eq!(m.into_inner(), 0b1001);
+    assert_eq!(m.remove(&m.clone()), Err(EmptyError));
+    assert_eq!(m.into_inner(), 0b1001);
+    assert_eq!(m.remove(&flag), Ok(()));
+    assert_eq!(m.into_inner(), 0b0001);
+}
+
+#[test]
+#[should_panic(expected = "Invalid result of `ShlAssign`")]
+fn bit_ops_validated_panic() {
+    let mut v = Nibble::try_from_inner(0b1000).unwrap();
+    v <<= 1;
+}
+
+#[test]
+fn from_str() {
+    let port: Port = "8080".parse().unwrap();
+    assert_eq!(port.into_inner(), 8080);
+
+    match "http".parse::<Port>() {
+        Err(FromStrError::Parse(_)) => {}
+        v => panic!("Unexpected result: {:?}", v),
+    }
+    assert_eq!(
+        "80".parse::<Port>(),
+        Err(FromStrError::Validation(PrivilegedPortError))
+    );
+    let err = "80".parse::<Port>().unwrap_err();
+    assert_eq!(err.to_string(), "Invalid inner value: privileged port");
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn convert_infallible() {
+    let v = MyInt::from(42);
+    assert_eq!(v.into_inner(), 42);
+    let v: MyInt = 42.into();
+    assert_eq!(i32::from(v), 42);
+    let inner: i32 = v.into();
+    assert_eq!(inner, 42);
+}
+
+#[test]
+fn convert_without_infallible_trait() {
+    let v = Count::from(3);
+    assert_eq!(v.into_inner(), 3);
+}
+
+#[test]
+fn convert_validated() {
+    let v = EvenInt::try_from(42).unwrap();
+    assert_eq!(i32::from(v), 42);
+    assert_eq!(EvenInt::try_from(43), Err(OddError));
+    let v: Result<EvenInt, _> = 44.try_into();
+    assert_eq!(v.map(i32::from), Ok(44));
+}
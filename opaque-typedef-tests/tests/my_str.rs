@@ -16,8 +16,16 @@ use opaque_typedef::{
 #[repr(transparent)]
 pub struct MyStr(str);
 
+/// String slice formatted in the same way as the inner string.
+#[derive(OpaqueTypedefUnsized, OpaqueTypedefUnsizedInfallible)]
+#[repr(transparent)]
+#[opaque_typedef(fmt(display, debug = "transparent"))]
+pub struct DisplayStr(str);
+
 /// My owned string.
 #[derive(Debug, Clone, OpaqueTypedefSized, OpaqueTypedefSizedMut, OpaqueTypedefSizedInfallible)]
+#[opaque_typedef(as_ref(inner, str), as_mut(inner, str))]
+#[opaque_typedef(deref(target = str, mut))]
 pub struct MyString(String);
 
 #[cfg(test)]
@@ -79,6 +87,19 @@ mod my_str {
     }
 }
 
+#[cfg(test)]
+mod display_str {
+    use super::*;
+
+    #[test]
+    fn fmt() {
+        let s = DisplayStr::from_inner("hello");
+        assert_eq!(format!("{}", s), "hello");
+        assert_eq!(format!("{:>7.3}", s), "    hel");
+        assert_eq!(format!("{:?}", s), "\"hello\"");
+    }
+}
+
 #[cfg(test)]
 mod my_string {
     use super::*;
@@ -90,6 +111,21 @@ mod my_string {
     {
     }
 
+    #[test]
+    fn as_mut() {
+        let mut my = MyString::from_inner("hello".to_owned());
+        AsMut::<String>::as_mut(&mut my).push_str(", world");
+        AsMut::<str>::as_mut(&mut my).make_ascii_uppercase();
+        assert_eq!(AsRef::<str>::as_ref(&my), "HELLO, WORLD");
+    }
+
+    #[test]
+    fn deref_mut() {
+        let mut my = MyString::from_inner("hello".to_owned());
+        my.make_ascii_uppercase();
+        assert_eq!(&*my, "HELLO");
+    }
+
     #[test]
     fn try_from_inner() {
         let inner = "hello".to_owned();
@@ -1,6 +1,8 @@
 //! Non-empty slice.
 
 use std::ops::{Bound, RangeBounds};
+use std::rc::Rc;
+use std::sync::Arc;
 
 use opaque_typedef::{OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut};
 
@@ -19,7 +21,9 @@ fn validate_slice<T>(v: &[T]) -> Result<&[T], Empty> {
 /// Non-empty slice.
 #[derive(Debug, OpaqueTypedefUnsized, OpaqueTypedefUnsizedMut)]
 #[repr(transparent)]
-#[opaque_typedef(validate(error = "Empty", validator = "validate_slice"))]
+#[opaque_typedef(allow_unchecked_mut)]
+#[opaque_typedef(validate(error = Empty, validator = validate_slice))]
+#[opaque_typedef(smart_ptr(box, rc, arc))]
 pub struct NonEmptySlice<T>([T]);
 
 impl<T> NonEmptySlice<T> {
@@ -69,6 +73,18 @@ mod non_empty_slice {
         assert!(NonEmptySlice::<i64>::new(&[]).is_err());
     }
 
+    #[test]
+    fn from_empty_mut() {
+        assert!(NonEmptySlice::<i64>::try_from_inner_mut(&mut []).is_err());
+    }
+
+    #[test]
+    fn from_nonempty_mut() {
+        let mut inner = [0, 1, 2];
+        let slice = NonEmptySlice::try_from_inner_mut(&mut inner).unwrap();
+        assert_eq!(slice.as_inner(), &[0, 1, 2]);
+    }
+
     #[test]
     fn subslice_empty() {
         let slice = NonEmptySlice::new(&[0, 1, 2, 4, 8]).unwrap();
@@ -84,4 +100,24 @@ mod non_empty_slice {
             expected.as_slice()
         );
     }
+
+    #[test]
+    fn smart_ptr() {
+        let slice = NonEmptySlice::new(&[0, 1, 2]).unwrap();
+        let boxed: Box<NonEmptySlice<i32>> = slice.into();
+        assert_eq!(boxed.clone().as_slice(), &[0, 1, 2]);
+        let rc: Rc<NonEmptySlice<i32>> = slice.into();
+        assert_eq!(rc.as_slice(), &[0, 1, 2]);
+        let arc: Arc<NonEmptySlice<i32>> = slice.into();
+        assert_eq!(arc.as_slice(), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn try_from_inner_box() {
+        let boxed = NonEmptySlice::try_from_inner_box(vec![0, 1, 2].into_boxed_slice()).unwrap();
+        assert_eq!(&*boxed.into_inner_box(), &[0, 1, 2]);
+        let (inner, err) = NonEmptySlice::<i32>::try_from_inner_box(Box::new([])).unwrap_err();
+        assert!(inner.is_empty());
+        assert_eq!(err, Empty);
+    }
 }
@@ -0,0 +1,71 @@
+//! Sorted slice, which requires additional bounds on the impls.
+
+use opaque_typedef::{OpaqueTypedefUnsized, TransparentUnsized};
+
+/// An error indicating the slice is not sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotSorted;
+
+fn validate_sorted<T: Ord>(v: &[T]) -> Result<&[T], NotSorted> {
+    if v.windows(2).all(|w| w[0] <= w[1]) {
+        Ok(v)
+    } else {
+        Err(NotSorted)
+    }
+}
+
+/// Sorted slice.
+///
+/// The struct itself has no bounds, but the validator requires `T: Ord`.
+#[derive(OpaqueTypedefUnsized)]
+#[repr(transparent)]
+#[opaque_typedef(validate(error = NotSorted, validator = validate_sorted))]
+#[opaque_typedef(bound = "T: Ord")]
+#[opaque_typedef(derive(PartialEq, Debug), bound(Debug = "T: std::fmt::Debug"))]
+#[opaque_typedef(smart_ptr(box))]
+pub struct SortedSlice<T>([T]);
+
+impl<T: Ord> SortedSlice<T> {
+    /// Returns the smallest element.
+    pub fn min(&self) -> Option<&T> {
+        self.as_inner().first()
+    }
+}
+
+/// A type which is comparable but not debug-printable.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct NoDebug(i32);
+
+fn debug_string<T: std::fmt::Debug + ?Sized>(v: &T) -> String {
+    format!("{:?}", v)
+}
+
+#[test]
+fn validate() {
+    let sorted = SortedSlice::try_from_inner(&[1, 2, 2, 3][..]).unwrap();
+    assert_eq!(sorted.min(), Some(&1));
+    assert_eq!(
+        SortedSlice::try_from_inner(&[3, 1, 2][..]).err(),
+        Some(NotSorted)
+    );
+    assert_eq!(
+        <SortedSlice<i32> as TransparentUnsized>::validate(&[2, 1]),
+        Err(NotSorted)
+    );
+}
+
+#[test]
+fn common_bound() {
+    let v = [NoDebug(1), NoDebug(3)];
+    let sorted = SortedSlice::try_from_inner(&v[..]).unwrap();
+    assert!(*sorted == *SortedSlice::try_from_inner(&v[..]).unwrap());
+    let boxed: Box<SortedSlice<NoDebug>> = sorted.into();
+    assert!(boxed.clone() == boxed);
+}
+
+#[test]
+fn trait_bound() {
+    let v = [1, 2];
+    let sorted = SortedSlice::try_from_inner(&v[..]).unwrap();
+    assert_eq!(debug_string(sorted), "SortedSlice([1, 2])");
+}
@@ -0,0 +1,111 @@
+//! Values tagged with zero-sized marker types.
+
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use opaque_typedef::{
+    OpaqueTypedefSized, OpaqueTypedefSizedInfallible, OpaqueTypedefUnsized,
+    OpaqueTypedefUnsizedInfallible,
+};
+
+/// Marker for user names.
+///
+/// Note that this implements no traits.
+pub enum UserName {}
+
+/// String slice with a marker type.
+#[derive(OpaqueTypedefUnsized, OpaqueTypedefUnsizedInfallible)]
+#[repr(C)]
+#[opaque_typedef(derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug))]
+pub struct TaggedStr<Tag> {
+    /// Tag.
+    tag: PhantomData<Tag>,
+    /// Inner data.
+    #[opaque_typedef(inner)]
+    inner: str,
+}
+
+#[test]
+fn from_inner() {
+    let s = TaggedStr::<UserName>::from_inner("alice");
+    assert_eq!(s.as_inner(), "alice");
+}
+
+#[test]
+fn try_from_inner() {
+    let s = TaggedStr::<UserName>::try_from_inner("bob").unwrap();
+    assert_eq!(s.as_inner(), "bob");
+}
+
+#[test]
+fn same_layout() {
+    let s = TaggedStr::<UserName>::from_inner("carol");
+    assert_eq!(std::mem::size_of_val(s), "carol".len());
+    assert_eq!(
+        s as *const TaggedStr<UserName> as *const u8,
+        s.as_inner().as_ptr()
+    );
+}
+
+/// ID with a marker type.
+#[derive(OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+#[opaque_typedef(derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug))]
+pub struct Id<Tag> {
+    /// Inner data.
+    #[opaque_typedef(inner)]
+    id: u64,
+    /// Tag.
+    tag: PhantomData<Tag>,
+}
+
+#[test]
+fn derive_unsized() {
+    let alice = TaggedStr::<UserName>::from_inner("alice");
+    let bob = TaggedStr::<UserName>::from_inner("bob");
+    assert_eq!(alice, TaggedStr::from_inner("alice"));
+    assert!(alice < bob);
+    assert_eq!(alice.cmp(bob), std::cmp::Ordering::Less);
+    assert_eq!(format!("{:?}", alice), r#"TaggedStr { inner: "alice" }"#);
+    let set = vec![alice, bob, alice].into_iter().collect::<HashSet<_>>();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn derive_sized() {
+    let id = Id::<UserName>::from_inner(42);
+    #[allow(clippy::clone_on_copy)]
+    let cloned = id.clone();
+    let copied = id;
+    assert_eq!(id, cloned);
+    assert_eq!(id, copied);
+    assert!(id < Id::from_inner(43));
+    assert_eq!(format!("{:?}", id), "Id { id: 42 }");
+    let set = vec![id, cloned, Id::from_inner(0)]
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(set.len(), 2);
+}
+
+/// Unit marker without `Default`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Meter;
+
+/// Length with a unit marker initialized explicitly.
+#[derive(OpaqueTypedefSized, OpaqueTypedefSizedInfallible)]
+#[opaque_typedef(derive(Clone, PartialEq, Debug))]
+pub struct Length {
+    /// Inner data.
+    #[opaque_typedef(inner)]
+    value: f64,
+    /// Unit.
+    #[opaque_typedef(init = Meter)]
+    unit: Meter,
+}
+
+#[test]
+fn init() {
+    let len = Length::from_inner(1.5);
+    assert_eq!(len.unit, Meter);
+    assert_eq!(len.clone(), len);
+    assert_eq!(Length::try_from_inner(2.0).map(|v| v.unit), Ok(Meter));
+}
@@ -0,0 +1,274 @@
+//! Conversions between pointers to unsized inner types and outer types.
+//!
+//! All pointer casts needed by the derived unsized traits live here, so that they can be audited
+//! at one place and the generated codes need no `unsafe` blocks.
+//! The functions are available for any type implementing [`TransparentUnsized`], and generic
+//! codes can rely on them being layout-compatible reinterpretations.
+
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A marker trait for an unsized type whose layout is same as its inner type.
+///
+/// This is implemented by `#[derive(OpaqueTypedefUnsized)]`, and it is not recommended to
+/// implement this trait manually.
+///
+/// # Safety
+///
+/// Implementors should guarantee all the conditions below.
+///
+/// * The type is a struct with `#[repr(transparent)]` or `#[repr(C)]`.
+/// * The last field of the type has the type `Self::Inner`, and `Self::Inner` is unsized.
+/// * All other fields are zero-sized and have alignment 1.
+/// * Any value of `Self::Inner` which passes [`validate`](#tymethod.validate) is a valid value
+///   of `Self`.
+///
+/// These conditions make a pointer to `Self::Inner` and a pointer to `Self` interchangeable,
+/// i.e. they have the same address and the same metadata, and the pointees have the same size
+/// and alignment.
+/// So references and smart pointers (such as `Box`, `Rc`, and `Arc`) can be converted between
+/// the inner type and the outer type by the functions in this module.
+///
+/// The first three conditions are also checked at compile time by the functions in this module
+/// as far as possible, but the implementors are still responsible for them.
+pub unsafe trait TransparentUnsized {
+    /// Inner unsized type.
+    type Inner: ?Sized;
+    /// Error type for validation of the inner value.
+    // `Error` requires `Debug` impl because `Result::expect()` does.
+    type Error: std::fmt::Debug;
+
+    /// Validates the inner value.
+    fn validate(inner: &Self::Inner) -> Result<(), Self::Error>;
+
+    /// Layout assertions specific to the type, evaluated at compile time on casts.
+    ///
+    /// The derive also evaluates them eagerly for non-generic types.
+    #[doc(hidden)]
+    const __ASSERT_LAYOUT: () = ();
+}
+
+/// Compile-time assertions of the layout.
+struct AssertLayout<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized + TransparentUnsized> AssertLayout<T> {
+    /// Assertions evaluated on monomorphization.
+    const OK: () = {
+        assert!(
+            mem::size_of::<*const T::Inner>() != mem::size_of::<*const ()>(),
+            "The inner type of `TransparentUnsized` type should be unsized"
+        );
+        assert!(
+            mem::size_of::<*const T>() == mem::size_of::<*const T::Inner>(),
+            "The pointer metadata of `TransparentUnsized` type should be same as the inner type"
+        );
+        T::__ASSERT_LAYOUT
+    };
+}
+
+/// Converts a pointer to the inner value into a pointer to the outer type.
+fn cast_ptr<T: ?Sized + TransparentUnsized>(ptr: *const T::Inner) -> *const T {
+    let () = AssertLayout::<T>::OK;
+    // Pointers to `T::Inner` and `T` have the same size, and the metadata of `T` is that of
+    // `T::Inner` because `T::Inner` is the unsized tail of `T`.
+    // Generic unsized pointers cannot be casted by `as`, so copy the pointer as is.
+    unsafe { mem::transmute_copy::<*const T::Inner, *const T>(&ptr) }
+}
+
+/// Converts a mutable pointer to the inner value into a mutable pointer to the outer type.
+fn cast_ptr_mut<T: ?Sized + TransparentUnsized>(ptr: *mut T::Inner) -> *mut T {
+    cast_ptr::<T>(ptr) as *mut T
+}
+
+/// Converts a pointer to the outer value into a pointer to the inner type.
+fn uncast_ptr<T: ?Sized + TransparentUnsized>(ptr: *const T) -> *const T::Inner {
+    let () = AssertLayout::<T>::OK;
+    // See `cast_ptr()`.
+    unsafe { mem::transmute_copy::<*const T, *const T::Inner>(&ptr) }
+}
+
+/// Converts a mutable pointer to the outer value into a mutable pointer to the inner type.
+fn uncast_ptr_mut<T: ?Sized + TransparentUnsized>(ptr: *mut T) -> *mut T::Inner {
+    uncast_ptr::<T>(ptr) as *mut T::Inner
+}
+
+/// Converts a reference to the inner value into the outer type, after validation.
+pub fn try_ref_from_inner<T: ?Sized + TransparentUnsized>(
+    inner: &T::Inner,
+) -> Result<&T, T::Error> {
+    T::validate(inner)?;
+    // The inner value is validated just now.
+    Ok(unsafe { ref_from_inner_unchecked(inner) })
+}
+
+/// Converts a mutable reference to the inner value into the outer type, after validation.
+pub fn try_mut_from_inner<T: ?Sized + TransparentUnsized>(
+    inner: &mut T::Inner,
+) -> Result<&mut T, T::Error> {
+    T::validate(inner)?;
+    // The inner value is validated just now.
+    Ok(unsafe { mut_from_inner_unchecked(inner) })
+}
+
+/// Converts a reference to the inner value into the outer type without validation.
+pub fn ref_from_inner<T: ?Sized + TransparentUnsized<Error = Infallible>>(inner: &T::Inner) -> &T {
+    // The validation never fails.
+    unsafe { ref_from_inner_unchecked(inner) }
+}
+
+/// Converts a mutable reference to the inner value into the outer type without validation.
+pub fn mut_from_inner<T: ?Sized + TransparentUnsized<Error = Infallible>>(
+    inner: &mut T::Inner,
+) -> &mut T {
+    // The validation never fails.
+    unsafe { mut_from_inner_unchecked(inner) }
+}
+
+/// Converts a reference to the inner value into the outer type without validation.
+///
+/// # Safety
+///
+/// The given inner value should be valid for the type, i.e. it should pass the validation.
+pub unsafe fn ref_from_inner_unchecked<T: ?Sized + TransparentUnsized>(inner: &T::Inner) -> &T {
+    &*cast_ptr::<T>(inner)
+}
+
+/// Converts a mutable reference to the inner value into the outer type without validation.
+///
+/// # Safety
+///
+/// The given inner value should be valid for the type, i.e. it should pass the validation.
+pub unsafe fn mut_from_inner_unchecked<T: ?Sized + TransparentUnsized>(
+    inner: &mut T::Inner,
+) -> &mut T {
+    &mut *cast_ptr_mut::<T>(inner)
+}
+
+/// Converts a reference to the outer value into the inner type.
+pub fn ref_into_inner<T: ?Sized + TransparentUnsized>(outer: &T) -> &T::Inner {
+    // Any valid value of `T` is a valid value of `T::Inner`.
+    unsafe { &*uncast_ptr::<T>(outer) }
+}
+
+/// Converts a boxed inner value into the outer type, after validation.
+///
+/// If the validation fails, the given box is returned with the error.
+#[allow(clippy::type_complexity)]
+pub fn try_box_from_inner<T: ?Sized + TransparentUnsized>(
+    inner: Box<T::Inner>,
+) -> Result<Box<T>, (Box<T::Inner>, T::Error)> {
+    match T::validate(&inner) {
+        // The inner value is validated just now.
+        Ok(()) => Ok(unsafe { box_from_inner_unchecked(inner) }),
+        Err(e) => Err((inner, e)),
+    }
+}
+
+/// Converts a boxed inner value into the outer type without validation.
+///
+/// # Safety
+///
+/// The given inner value should be valid for the type, i.e. it should pass the validation.
+pub unsafe fn box_from_inner_unchecked<T: ?Sized + TransparentUnsized>(
+    inner: Box<T::Inner>,
+) -> Box<T> {
+    Box::from_raw(cast_ptr_mut::<T>(Box::into_raw(inner)))
+}
+
+/// Converts a boxed outer value into the inner type.
+pub fn box_into_inner<T: ?Sized + TransparentUnsized>(outer: Box<T>) -> Box<T::Inner> {
+    // Any valid value of `T` is a valid value of `T::Inner`.
+    unsafe { Box::from_raw(uncast_ptr_mut::<T>(Box::into_raw(outer))) }
+}
+
+/// Copies the outer value into a new box.
+pub fn box_from_ref<'a, T: ?Sized + TransparentUnsized>(outer: &'a T) -> Box<T>
+where
+    Box<T::Inner>: From<&'a T::Inner>,
+{
+    // The copy of the valid value is also valid.
+    unsafe { box_from_inner_unchecked(Box::from(ref_into_inner(outer))) }
+}
+
+/// Converts a reference-counted inner value into the outer type, after validation.
+///
+/// If the validation fails, the given pointer is returned with the error.
+#[allow(clippy::type_complexity)]
+pub fn try_rc_from_inner<T: ?Sized + TransparentUnsized>(
+    inner: Rc<T::Inner>,
+) -> Result<Rc<T>, (Rc<T::Inner>, T::Error)> {
+    match T::validate(&inner) {
+        // The inner value is validated just now.
+        Ok(()) => Ok(unsafe { rc_from_inner_unchecked(inner) }),
+        Err(e) => Err((inner, e)),
+    }
+}
+
+/// Converts a reference-counted inner value into the outer type without validation.
+///
+/// # Safety
+///
+/// The given inner value should be valid for the type, i.e. it should pass the validation.
+pub unsafe fn rc_from_inner_unchecked<T: ?Sized + TransparentUnsized>(
+    inner: Rc<T::Inner>,
+) -> Rc<T> {
+    Rc::from_raw(cast_ptr::<T>(Rc::into_raw(inner)))
+}
+
+/// Converts a reference-counted outer value into the inner type.
+pub fn rc_into_inner<T: ?Sized + TransparentUnsized>(outer: Rc<T>) -> Rc<T::Inner> {
+    // Any valid value of `T` is a valid value of `T::Inner`.
+    unsafe { Rc::from_raw(uncast_ptr::<T>(Rc::into_raw(outer))) }
+}
+
+/// Copies the outer value into a new reference-counted pointer.
+pub fn rc_from_ref<'a, T: ?Sized + TransparentUnsized>(outer: &'a T) -> Rc<T>
+where
+    Rc<T::Inner>: From<&'a T::Inner>,
+{
+    // The copy of the valid value is also valid.
+    unsafe { rc_from_inner_unchecked(Rc::from(ref_into_inner(outer))) }
+}
+
+/// Converts an atomically reference-counted inner value into the outer type, after validation.
+///
+/// If the validation fails, the given pointer is returned with the error.
+#[allow(clippy::type_complexity)]
+pub fn try_arc_from_inner<T: ?Sized + TransparentUnsized>(
+    inner: Arc<T::Inner>,
+) -> Result<Arc<T>, (Arc<T::Inner>, T::Error)> {
+    match T::validate(&inner) {
+        // The inner value is validated just now.
+        Ok(()) => Ok(unsafe { arc_from_inner_unchecked(inner) }),
+        Err(e) => Err((inner, e)),
+    }
+}
+
+/// Converts an atomically reference-counted inner value into the outer type without validation.
+///
+/// # Safety
+///
+/// The given inner value should be valid for the type, i.e. it should pass the validation.
+pub unsafe fn arc_from_inner_unchecked<T: ?Sized + TransparentUnsized>(
+    inner: Arc<T::Inner>,
+) -> Arc<T> {
+    Arc::from_raw(cast_ptr::<T>(Arc::into_raw(inner)))
+}
+
+/// Converts an atomically reference-counted outer value into the inner type.
+pub fn arc_into_inner<T: ?Sized + TransparentUnsized>(outer: Arc<T>) -> Arc<T::Inner> {
+    // Any valid value of `T` is a valid value of `T::Inner`.
+    unsafe { Arc::from_raw(uncast_ptr::<T>(Arc::into_raw(outer))) }
+}
+
+/// Copies the outer value into a new atomically reference-counted pointer.
+pub fn arc_from_ref<'a, T: ?Sized + TransparentUnsized>(outer: &'a T) -> Arc<T>
+where
+    Arc<T::Inner>: From<&'a T::Inner>,
+{
+    // The copy of the valid value is also valid.
+    unsafe { arc_from_inner_unchecked(Arc::from(ref_into_inner(outer))) }
+}
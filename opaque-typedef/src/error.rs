@@ -0,0 +1,37 @@
+//! Error types.
+
+use std::error::Error;
+use std::fmt;
+
+/// An error for parsing an opaque typedef from a string.
+///
+/// This is the error type of `FromStr` impls generated by `#[opaque_typedef(from_str)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FromStrError<P, V> {
+    /// Failed to parse the inner value.
+    Parse(P),
+    /// The parsed inner value is invalid.
+    Validation(V),
+}
+
+impl<P: fmt::Display, V: fmt::Display> fmt::Display for FromStrError<P, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromStrError::Parse(e) => write!(f, "Failed to parse the inner value: {}", e),
+            FromStrError::Validation(e) => write!(f, "Invalid inner value: {}", e),
+        }
+    }
+}
+
+impl<P, V> Error for FromStrError<P, V>
+where
+    P: Error + 'static,
+    V: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FromStrError::Parse(e) => Some(e),
+            FromStrError::Validation(e) => Some(e),
+        }
+    }
+}
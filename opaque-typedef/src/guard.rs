@@ -0,0 +1,65 @@
+//! Guard for validated mutable access to the inner value.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::OpaqueTypedefSized;
+
+/// A guard of mutable access to the inner value of an opaque sized type.
+///
+/// The guard works on a clone of the inner value, and the modification is applied to the target
+/// only when [`commit`](#method.commit) succeeds.
+/// If the guard is dropped without being committed, the modification is discarded.
+pub struct InnerMutGuard<'a, T: OpaqueTypedefSized> {
+    /// Target value.
+    target: &'a mut T,
+    /// Working copy of the inner value.
+    inner: T::Inner,
+}
+
+impl<'a, T: OpaqueTypedefSized> InnerMutGuard<'a, T>
+where
+    T::Inner: Clone,
+{
+    /// Creates a new guard for the given value.
+    pub fn new(target: &'a mut T) -> Self {
+        let inner = target.as_inner().clone();
+        Self { target, inner }
+    }
+}
+
+impl<'a, T: OpaqueTypedefSized> InnerMutGuard<'a, T> {
+    /// Validates the modified inner value and applies it to the target.
+    ///
+    /// If the validation fails, the target is left unmodified and the error is returned.
+    pub fn commit(self) -> Result<&'a mut T, T::Error> {
+        let Self { target, inner } = self;
+        *target = T::try_from_inner(inner)?;
+        Ok(target)
+    }
+}
+
+impl<T: OpaqueTypedefSized> Deref for InnerMutGuard<'_, T> {
+    type Target = T::Inner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T: OpaqueTypedefSized> DerefMut for InnerMutGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<T: OpaqueTypedefSized> fmt::Debug for InnerMutGuard<'_, T>
+where
+    T::Inner: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InnerMutGuard")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
@@ -0,0 +1,51 @@
+//! Comparison keys.
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+/// A key to compare byte strings in ASCII case-insensitive manner.
+///
+/// This is the key used by `#[opaque_typedef(cmp(ascii_case_insensitive))]`.
+/// `PartialEq`, `Eq`, `PartialOrd`, `Ord`, and `Hash` are consistent with each other.
+#[derive(Debug, Clone, Copy)]
+pub struct AsciiCaseInsensitive<'a>(&'a [u8]);
+
+impl<'a> AsciiCaseInsensitive<'a> {
+    /// Creates a new key from the given byte string.
+    pub fn new<T: ?Sized + AsRef<[u8]>>(v: &'a T) -> Self {
+        Self(v.as_ref())
+    }
+
+    /// Returns an iterator of the bytes in ASCII lowercase.
+    fn lowercase_bytes(&self) -> impl Iterator<Item = u8> + 'a {
+        self.0.iter().map(u8::to_ascii_lowercase)
+    }
+}
+
+impl PartialEq for AsciiCaseInsensitive<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(other.0)
+    }
+}
+
+impl Eq for AsciiCaseInsensitive<'_> {}
+
+impl PartialOrd for AsciiCaseInsensitive<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AsciiCaseInsensitive<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.lowercase_bytes().cmp(other.lowercase_bytes())
+    }
+}
+
+impl Hash for AsciiCaseInsensitive<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Write the length first, same as the slices do.
+        state.write_usize(self.0.len());
+        self.lowercase_bytes().for_each(|b| state.write_u8(b));
+    }
+}
@@ -12,6 +12,19 @@ extern crate opaque_typedef_macros;
 #[doc(hidden)]
 pub use opaque_typedef_macros::*;
 
+use std::borrow::Borrow;
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub use self::{
+    cast::TransparentUnsized, error::FromStrError, guard::InnerMutGuard, key::AsciiCaseInsensitive,
+};
+
+pub mod cast;
+mod error;
+mod guard;
+mod key;
+
 /// A trait for an opaque type alias of a sized type.
 pub trait OpaqueTypedefSized: Sized {
     /// Inner sized type.
@@ -23,11 +36,37 @@ pub trait OpaqueTypedefSized: Sized {
     /// Creates a new value from the given inner value.
     fn try_from_inner(inner: Self::Inner) -> Result<Self, Self::Error>;
     /// Creates a new value without validation.
+    ///
+    /// # Safety
+    ///
+    /// The given inner value should be valid for the type, i.e. it should pass the validation.
     unsafe fn from_inner_unchecked(inner: Self::Inner) -> Self;
     /// Returns the inner value.
     fn into_inner(self) -> Self::Inner;
     /// Returns a reference to the inner value.
     fn as_inner(&self) -> &Self::Inner;
+
+    /// Returns a guard of mutable access to the inner value, which validates on commit.
+    fn inner_mut_guard(&mut self) -> InnerMutGuard<'_, Self>
+    where
+        Self::Inner: Clone,
+    {
+        InnerMutGuard::new(self)
+    }
+
+    /// Modifies the inner value by the given function, and validates the result.
+    ///
+    /// If the validation fails, the value is left unmodified and the error is returned.
+    fn try_modify_inner<F, R>(&mut self, f: F) -> Result<R, Self::Error>
+    where
+        Self::Inner: Clone,
+        F: FnOnce(&mut Self::Inner) -> R,
+    {
+        let mut guard = self.inner_mut_guard();
+        let ret = f(&mut guard);
+        guard.commit()?;
+        Ok(ret)
+    }
 }
 
 /// A trait for an opaque type alias of a sized type creatable with infallible conversion.
@@ -40,42 +79,160 @@ pub trait OpaqueTypedefSizedInfallible:
 
 /// A trait for an opaque type alias of a mutable sized type.
 pub trait OpaqueTypedefSizedMut: OpaqueTypedefSized {
+    /// Returns a mutable reference to the inner value, without validation.
+    ///
+    /// # Safety
+    ///
+    /// The inner value should be kept valid for the type, i.e. it should pass the validation
+    /// when the returned reference is released.
+    unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner;
+
     /// Returns a mutable reference to the inner value.
-    fn as_inner_mut(&mut self) -> &mut Self::Inner;
+    ///
+    /// This is available only for types whose conversion from the inner value never fails.
+    /// For validated types, use [`OpaqueTypedefSized::try_modify_inner`] instead.
+    fn as_inner_mut(&mut self) -> &mut Self::Inner
+    where
+        Self: OpaqueTypedefSized<Error = std::convert::Infallible>,
+    {
+        // Any inner value is valid, because the conversion from the inner value is infallible.
+        unsafe { self.as_inner_mut_unchecked() }
+    }
 }
 
-/// A trait for an opaque type alias of an unsized type.
-pub trait OpaqueTypedefUnsized {
-    /// Inner unsized type.
-    type Inner: ?Sized;
+/// A trait for an opaque type alias of multiple sized fields.
+///
+/// The inner type is the tuple of the inner fields, so the validation can check the invariant
+/// spanning the fields.
+/// Unlike [`OpaqueTypedefSized`], the inner tuple is not stored as is and a reference to it is
+/// not available. The derive generates getters for each inner field instead.
+pub trait OpaqueTypedefComposite: Sized {
+    /// Tuple of the inner field types.
+    type Inner: Sized;
     /// Error type for conversion from the inner type.
-    // `Error` requires `Debug` impl because `Result::expect()` does.
     type Error: std::fmt::Debug;
 
     /// Creates a new value from the given inner value.
-    fn try_from_inner(inner: &Self::Inner) -> Result<&Self, Self::Error>;
+    fn try_from_inner(inner: Self::Inner) -> Result<Self, Self::Error>;
     /// Creates a new value without validation.
-    unsafe fn from_inner_unchecked(inner: &Self::Inner) -> &Self;
+    ///
+    /// # Safety
+    ///
+    /// The given inner value should be valid for the type, i.e. it should pass the validation.
+    unsafe fn from_inner_unchecked(inner: Self::Inner) -> Self;
+    /// Returns the inner value.
+    fn into_inner(self) -> Self::Inner;
+}
+
+/// A trait for an opaque type alias of an unsized type.
+///
+/// The inner type and the error type are defined by the supertrait [`TransparentUnsized`],
+/// which guarantees that the type has the same layout as the inner type.
+/// The conversions from the inner type are provided by the functions in [`cast`] module.
+///
+/// Note that the methods of this trait can be overridden by the implementors.
+/// Generic codes which rely on the conversions being layout-compatible reinterpretations
+/// should use the functions in [`cast`] module directly.
+pub trait OpaqueTypedefUnsized: TransparentUnsized {
+    /// Creates a new value from the given inner value.
+    fn try_from_inner(inner: &Self::Inner) -> Result<&Self, Self::Error> {
+        cast::try_ref_from_inner(inner)
+    }
+    /// Creates a new value without validation.
+    ///
+    /// # Safety
+    ///
+    /// The given inner value should be valid for the type, i.e. it should pass the validation.
+    unsafe fn from_inner_unchecked(inner: &Self::Inner) -> &Self {
+        cast::ref_from_inner_unchecked(inner)
+    }
     /// Returns a reference to the inner value.
     fn as_inner(&self) -> &Self::Inner;
+
+    /// Creates a new boxed value from the given boxed inner value.
+    ///
+    /// If the validation fails, the given box is returned with the error.
+    #[allow(clippy::type_complexity)]
+    fn try_from_inner_box(
+        inner: Box<Self::Inner>,
+    ) -> Result<Box<Self>, (Box<Self::Inner>, Self::Error)> {
+        cast::try_box_from_inner(inner)
+    }
+    /// Returns the boxed inner value.
+    fn into_inner_box(self: Box<Self>) -> Box<Self::Inner> {
+        cast::box_into_inner(self)
+    }
+    /// Creates a new reference-counted value from the given reference-counted inner value.
+    ///
+    /// If the validation fails, the given pointer is returned with the error.
+    #[allow(clippy::type_complexity)]
+    fn try_from_inner_rc(
+        inner: Rc<Self::Inner>,
+    ) -> Result<Rc<Self>, (Rc<Self::Inner>, Self::Error)> {
+        cast::try_rc_from_inner(inner)
+    }
+    /// Returns the reference-counted inner value.
+    fn into_inner_rc(self: Rc<Self>) -> Rc<Self::Inner> {
+        cast::rc_into_inner(self)
+    }
+    /// Creates a new atomically reference-counted value from the given atomically
+    /// reference-counted inner value.
+    ///
+    /// If the validation fails, the given pointer is returned with the error.
+    #[allow(clippy::type_complexity)]
+    fn try_from_inner_arc(
+        inner: Arc<Self::Inner>,
+    ) -> Result<Arc<Self>, (Arc<Self::Inner>, Self::Error)> {
+        cast::try_arc_from_inner(inner)
+    }
+    /// Returns the atomically reference-counted inner value.
+    fn into_inner_arc(self: Arc<Self>) -> Arc<Self::Inner> {
+        cast::arc_into_inner(self)
+    }
 }
 
 /// A trait for an opaque type alias of a unsized type creatable with infallible conversion.
 pub trait OpaqueTypedefUnsizedInfallible:
-    OpaqueTypedefUnsized<Error = std::convert::Infallible>
+    OpaqueTypedefUnsized + TransparentUnsized<Error = std::convert::Infallible>
 {
     /// Creates a new value from the given inner value, without possibility of failure.
-    fn from_inner(inner: &Self::Inner) -> &Self;
+    fn from_inner(inner: &Self::Inner) -> &Self {
+        cast::ref_from_inner(inner)
+    }
 }
 
 /// A trait for an opaque type alias of a mutable unsized type.
 pub trait OpaqueTypedefUnsizedMut: OpaqueTypedefUnsized {
     /// Creates a new value from the given inner value.
-    fn try_from_inner_mut(inner: &mut Self::Inner) -> Result<&mut Self, Self::Error>;
+    fn try_from_inner_mut(inner: &mut Self::Inner) -> Result<&mut Self, Self::Error> {
+        cast::try_mut_from_inner(inner)
+    }
     /// Creates a new value without validation.
-    unsafe fn from_inner_unchecked_mut(inner: &mut Self::Inner) -> &mut Self;
-    /// Returns a mutable reference to the inner slice.
-    fn as_inner_mut(&mut self) -> &mut Self::Inner;
+    ///
+    /// # Safety
+    ///
+    /// The given inner value should be valid for the type, i.e. it should pass the validation.
+    unsafe fn from_inner_unchecked_mut(inner: &mut Self::Inner) -> &mut Self {
+        cast::mut_from_inner_unchecked(inner)
+    }
+    /// Returns a mutable reference to the inner value, without validation.
+    ///
+    /// # Safety
+    ///
+    /// The inner value should be kept valid for the type, i.e. it should pass the validation
+    /// when the returned reference is released.
+    unsafe fn as_inner_mut_unchecked(&mut self) -> &mut Self::Inner;
+
+    /// Returns a mutable reference to the inner value.
+    ///
+    /// This is available only for types whose conversion from the inner value never fails.
+    fn as_inner_mut(&mut self) -> &mut Self::Inner
+    where
+        Self: TransparentUnsized<Error = std::convert::Infallible>,
+    {
+        // Any inner value is valid, because the conversion from the inner value is infallible.
+        unsafe { self.as_inner_mut_unchecked() }
+    }
 }
 
 /// A trait for an opaque type alias of a unsized type creatable with infallible conversion.
@@ -83,5 +240,47 @@ pub trait OpaqueTypedefUnsizedInfallibleMut:
     OpaqueTypedefUnsizedMut + OpaqueTypedefUnsizedInfallible
 {
     /// Creates a new value from the given inner value, without possibility of failure.
-    fn from_inner_mut(inner: &mut Self::Inner) -> &mut Self;
+    fn from_inner_mut(inner: &mut Self::Inner) -> &mut Self {
+        cast::mut_from_inner(inner)
+    }
+}
+
+/// A trait for an opaque type alias of a sized type, which has an unsized type as its borrowed
+/// form.
+///
+/// This is implemented by `#[opaque_typedef(owned = ..)]` and `#[opaque_typedef(borrowed = ..)]`,
+/// and it is not recommended to implement this trait manually.
+///
+/// # Safety
+///
+/// Implementors should guarantee the conditions below.
+///
+/// * For any valid value of `Self`, the inner value borrowed as the inner type of
+///   `Self::Borrowed` is valid for `Self::Borrowed`.
+/// * For any valid value of `Self::Borrowed`, the owned inner value created by `ToOwned` is valid
+///   for `Self`.
+///
+/// In other words, `Self` and `Self::Borrowed` should have the same validation.
+/// The conversions between them skip the validation.
+pub unsafe trait OpaqueTypedefOwned: OpaqueTypedefSized {
+    /// Borrowed type.
+    type Borrowed: ?Sized + OpaqueTypedefUnsized;
+
+    /// Returns the value as the borrowed type.
+    fn as_borrowed(&self) -> &Self::Borrowed
+    where
+        Self::Inner: Borrow<<Self::Borrowed as TransparentUnsized>::Inner>,
+    {
+        // The borrowed inner value is valid for `Self::Borrowed`.
+        unsafe { cast::ref_from_inner_unchecked(self.as_inner().borrow()) }
+    }
+
+    /// Creates a new owned value from the borrowed value.
+    fn from_borrowed(borrowed: &Self::Borrowed) -> Self
+    where
+        <Self::Borrowed as TransparentUnsized>::Inner: ToOwned<Owned = Self::Inner>,
+    {
+        // The owned inner value is valid for `Self`.
+        unsafe { Self::from_inner_unchecked(cast::ref_into_inner(borrowed).to_owned()) }
+    }
 }